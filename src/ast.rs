@@ -1,22 +1,43 @@
 use std::ops::Range;
 
-#[derive(Clone, Debug)]
-pub struct Src(pub(crate) Range<usize>);
+use crate::source::FileId;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Src {
+  pub file: FileId,
+  pub range: Range<usize>,
+}
 
 impl Src {
-  pub fn new(start: usize, end: usize) -> Self {
-    Self(start..end)
+  pub fn new(file: FileId, start: usize, end: usize) -> Self {
+    Self {
+      file,
+      range: start..end,
+    }
   }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Literal {
-  Number { value: i32 },
-  String { value: String },
-  Boolean { value: bool },
+  /// Saturates at `i64::MAX`, out of range values are reported later.
+  Integer {
+    value: i64,
+  },
+  Float {
+    value: f32,
+  },
+  String {
+    value: String,
+  },
+  Char {
+    value: char,
+  },
+  Boolean {
+    value: bool,
+  },
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum ExpressionType {
   /// "()"
   Unit,
@@ -24,9 +45,9 @@ pub enum ExpressionType {
   Hole { name: String },
   /// a..z | _
   Variable { name: String },
-  /// fun var -> body
+  /// fun pattern ... -> body
   Fun {
-    variable: String,
+    parameters: Vec<Pattern>,
     body: Box<Expression>,
   },
   /// f x
@@ -34,11 +55,15 @@ pub enum ExpressionType {
     function: Box<Expression>,
     argument: Box<Expression>,
   },
-  /// num | str | bool
+  /// num | str | char | bool
   Literal { literal: Literal },
-  /// let bind = value in next
+  /// "text {name} text"
+  ///
+  /// The parts are string literals and the interpolated variables.
+  Interpolation { parts: Vec<Expression> },
+  /// let pattern = value in next
   Let {
-    bind: String,
+    bind: Pattern,
     value: Box<Expression>,
     next: Box<Expression>,
   },
@@ -56,19 +81,45 @@ pub enum ExpressionType {
     scrutinee: Box<Expression>,
     arms: Vec<Arm>,
   },
+  /// operand op operand op ...
+  ///
+  /// Operators chained as written, grouped by their fixities once the
+  /// program is parsed.
+  Infix {
+    first: Box<Expression>,
+    rest: Vec<(Operator, Expression)>,
+  },
+  /// (op) | (op right) | (left op)
+  Section {
+    operator: Operator,
+    left: Option<Box<Expression>>,
+    right: Option<Box<Expression>>,
+  },
   /// lhs op rhs
   BinaryOp {
     op: Operation,
     lhs: Box<Expression>,
     rhs: Box<Expression>,
   },
+  /// op operand
+  UnaryOp {
+    op: UnaryOperation,
+    operand: Box<Expression>,
+  },
   /// .variant
   Variant { variant: String },
   /// (...,)
   Tuple { elements: Vec<Expression> },
+  /// tuple.index
+  Projection {
+    tuple: Box<Expression>,
+    index: usize,
+  },
+  /// [...,]
+  List { elements: Vec<Expression> },
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Spanned<T> {
   pub data: Box<T>,
   pub src: Src,
@@ -76,6 +127,44 @@ pub struct Spanned<T> {
 
 pub type Expression = Spanned<ExpressionType>;
 
+/// The symbol of an infix operator.
+pub type Operator = Spanned<String>;
+
+/// A name being defined, where it is written.
+pub type Binder = Spanned<String>;
+
+impl Expression {
+  /// `first op operand ...`, or just `first` when there are no operators.
+  pub fn infix(first: Expression, rest: Vec<(Operator, Expression)>, src: Src) -> Expression {
+    if rest.is_empty() {
+      return first;
+    }
+    Spanned::new(
+      ExpressionType::Infix {
+        first: Box::new(first),
+        rest,
+      },
+      src,
+    )
+  }
+
+  /// `-operand`, folded into the literal when negating a number literal.
+  pub fn negate(operand: Expression, src: Src) -> Expression {
+    let data = match *operand.data {
+      ExpressionType::Literal {
+        literal: literal @ (Literal::Integer { .. } | Literal::Float { .. }),
+      } => ExpressionType::Literal {
+        literal: literal.negate(),
+      },
+      data => ExpressionType::UnaryOp {
+        op: UnaryOperation::Neg,
+        operand: Box::new(Spanned::new(data, operand.src)),
+      },
+    };
+    Spanned::new(data, src)
+  }
+}
+
 impl<T> Spanned<T> {
   pub fn new(data: T, src: Src) -> Self {
     Self {
@@ -89,12 +178,19 @@ impl<T> Spanned<T> {
   }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Operation {
   Add,
   Sub,
   Mul,
   Div,
+  Rem,
+  Pow,
+  BitAnd,
+  BitOr,
+  BitXor,
+  Shl,
+  Shr,
   Gt,
   Gte,
   Lt,
@@ -102,64 +198,211 @@ pub enum Operation {
   Eq,
   Neq,
   Concat,
+  Cons,
+  And,
+  Or,
 }
 
-#[derive(Clone, Debug)]
+impl Operation {
+  /// The builtin operator written as `symbol`.
+  pub fn from_symbol(symbol: &str) -> Option<Operation> {
+    let op = match symbol {
+      "+" => Operation::Add,
+      "-" => Operation::Sub,
+      "*" => Operation::Mul,
+      "/" => Operation::Div,
+      "%" => Operation::Rem,
+      "**" => Operation::Pow,
+      "&" => Operation::BitAnd,
+      "|" => Operation::BitOr,
+      "^" => Operation::BitXor,
+      "<<" => Operation::Shl,
+      ">>" => Operation::Shr,
+      ">" => Operation::Gt,
+      ">=" => Operation::Gte,
+      "<" => Operation::Lt,
+      "<=" => Operation::Lte,
+      "=" => Operation::Eq,
+      "<>" => Operation::Neq,
+      "++" => Operation::Concat,
+      "::" => Operation::Cons,
+      "&&" => Operation::And,
+      "||" => Operation::Or,
+      _ => return None,
+    };
+    Some(op)
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnaryOperation {
+  /// -x
+  Neg,
+  /// not x
+  Not,
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Arm {
   pub left: Pattern,
   pub right: Expression,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum PatternType {
-  Variable { name: String },
-  Variant { variant: String },
-  Literal { literal: Literal },
-  Tuple { binds: Vec<String> },
+  Variable {
+    name: String,
+  },
+  Variant {
+    variant: String,
+  },
+  Literal {
+    literal: Literal,
+  },
+  Tuple {
+    elements: Vec<Pattern>,
+  },
+  /// []
+  Nil,
+  /// head :: tail
+  Cons {
+    head: Pattern,
+    tail: Pattern,
+  },
 }
 
 pub type Pattern = Spanned<PatternType>;
 
-pub type Parameters = Vec<String>;
+impl PatternType {
+  /// Whether some values of the pattern's type don't match it.
+  pub fn is_refutable(&self) -> bool {
+    match self {
+      PatternType::Variable { .. } => false,
+      PatternType::Tuple { elements } => elements.iter().any(|e| e.data.is_refutable()),
+      _ => true,
+    }
+  }
+}
+
+pub type Parameters = Vec<Binder>;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Function {
+  pub public: bool,
   pub name: String,
   pub rec: bool,
   pub parameters: Parameters,
   pub body: Expression,
+  pub src: Src,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Enum {
+  pub public: bool,
   pub name: String,
   pub parameters: Vec<String>,
-  pub variants: Vec<String>,
+  pub variants: Vec<Binder>,
+  pub src: Src,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum TopLevel {
   Function(Function),
   Enum(Enum),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Assoc {
+  Left,
+  Right,
+  None,
+}
+
+/// How tightly an operator binds, higher precedences binding tighter.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fixity {
+  pub assoc: Assoc,
+  pub precedence: u8,
+}
+
+/// infixl precedence op
+#[derive(Clone, Debug, PartialEq)]
+pub struct FixityDecl {
+  pub operator: String,
+  pub fixity: Fixity,
+  pub src: Src,
+}
+
+/// A declaration as it appears in the source.
+pub enum Declaration {
+  TopLevel(TopLevel),
+  Fixity(FixityDecl),
+}
+
+/// import module
+/// import module.{name, ...}
+#[derive(Clone, Debug, PartialEq)]
+pub struct Import {
+  pub module: String,
+  pub names: Option<Vec<String>>,
+  pub src: Src,
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Program {
   pub file_name: Option<Box<str>>,
+  pub imports: Vec<Import>,
+  pub fixities: Vec<FixityDecl>,
   pub declarations: Vec<TopLevel>,
 }
 
+impl Literal {
+  /// Parses the digits of an integer literal, ignoring `_` separators.
+  pub fn integer(digits: &str, radix: u32) -> Self {
+    let digits = digits.replace('_', "");
+    let value = i64::from_str_radix(&digits, radix).unwrap_or(i64::MAX);
+    Literal::Integer { value }
+  }
+
+  pub fn float(digits: &str) -> Self {
+    let value = digits.replace('_', "").parse().unwrap_or(f32::INFINITY);
+    Literal::Float { value }
+  }
+
+  pub fn negate(self) -> Self {
+    match self {
+      Literal::Integer { value } => Literal::Integer {
+        value: value.saturating_neg(),
+      },
+      Literal::Float { value } => Literal::Float { value: -value },
+      literal => literal,
+    }
+  }
+}
+
 impl Program {
   pub fn empty() -> Self {
     Self {
       file_name: None,
+      imports: Vec::new(),
+      fixities: Vec::new(),
       declarations: Vec::new(),
     }
   }
 
-  pub fn new(declarations: Vec<TopLevel>) -> Self {
+  pub fn new(imports: Vec<Import>, items: Vec<Declaration>) -> Self {
+    let mut fixities = Vec::new();
+    let mut declarations = Vec::new();
+    for item in items {
+      match item {
+        Declaration::TopLevel(declaration) => declarations.push(declaration),
+        Declaration::Fixity(fixity) => fixities.push(fixity),
+      }
+    }
     Self {
       file_name: None,
+      imports,
+      fixities,
       declarations,
     }
   }
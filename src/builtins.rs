@@ -0,0 +1,167 @@
+use crate::{
+  arr,
+  checker::types::{Scheme, Type, TypeKind},
+};
+
+/// A primitive implemented in Bend, in `oktu.builtins.bend`.
+pub struct Builtin {
+  /// The name the primitive has in Oktu programs.
+  pub name: &'static str,
+  /// The Bend definition implementing it.
+  pub bend: &'static str,
+  pub scheme: fn() -> Scheme,
+}
+
+/// Every primitive. Adding one takes an entry here, its definition in
+/// `oktu.builtins.bend` and a case in the interpreter's `Evaluator::call`,
+/// in `elab/eval.rs`.
+pub const BUILTINS: &[Builtin] = &[
+  Builtin {
+    name: "print",
+    bend: "print",
+    scheme: || mono(arr!(TypeKind::String => TypeKind::Unit)),
+  },
+  Builtin {
+    name: "string_concat",
+    bend: "_Oktu_/String/concat",
+    scheme: || mono(TypeKind::str_str()),
+  },
+  Builtin {
+    name: "string_length",
+    bend: "_Oktu_/String/length",
+    scheme: || mono(arr!(TypeKind::String => TypeKind::I24)),
+  },
+  Builtin {
+    name: "substring",
+    bend: "_Oktu_/String/substring",
+    scheme: || {
+      mono(arr!(TypeKind::String => arr!(TypeKind::I24 => arr!(TypeKind::I24 => TypeKind::String))))
+    },
+  },
+  Builtin {
+    name: "char_at",
+    bend: "_Oktu_/String/char_at",
+    scheme: || mono(arr!(TypeKind::String => arr!(TypeKind::I24 => TypeKind::Char))),
+  },
+  Builtin {
+    name: "string_cons",
+    bend: "_Oktu_/String/cons",
+    scheme: || mono(arr!(TypeKind::Char => arr!(TypeKind::String => TypeKind::String))),
+  },
+  Builtin {
+    name: "string_uncons",
+    bend: "_Oktu_/String/uncons",
+    scheme: || {
+      let pair = TypeKind::Tuple {
+        elements: vec![TypeKind::char(), TypeKind::string()],
+      };
+      mono(arr!(TypeKind::String => TypeKind::list(pair.into())))
+    },
+  },
+  Builtin {
+    name: "char_to_u24",
+    bend: "_Oktu_/Char/to_u24",
+    scheme: || mono(arr!(TypeKind::Char => TypeKind::U24)),
+  },
+  Builtin {
+    name: "u24_to_char",
+    bend: "_Oktu_/Char/from_u24",
+    scheme: || mono(arr!(TypeKind::U24 => TypeKind::Char)),
+  },
+  Builtin {
+    name: "string_compare",
+    bend: "_Oktu_/String/compare",
+    scheme: || mono(arr!(TypeKind::String => arr!(TypeKind::String => TypeKind::I24))),
+  },
+  Builtin {
+    name: "string_equal",
+    bend: "_Oktu_/String/equal",
+    scheme: || mono(arr!(TypeKind::String => arr!(TypeKind::String => TypeKind::Boolean))),
+  },
+  Builtin {
+    name: "number_to_string",
+    bend: "_Oktu_/Number/to_string",
+    scheme: || mono(arr!(TypeKind::I24 => TypeKind::String)),
+  },
+  Builtin {
+    name: "string_to_number",
+    bend: "_Oktu_/Number/from_string",
+    scheme: || mono(arr!(TypeKind::String => TypeKind::list(TypeKind::i24()))),
+  },
+  Builtin {
+    name: "min",
+    bend: "_Oktu_/Number/min",
+    scheme: || mono(TypeKind::same_same(TypeKind::i24())),
+  },
+  Builtin {
+    name: "max",
+    bend: "_Oktu_/Number/max",
+    scheme: || mono(TypeKind::same_same(TypeKind::i24())),
+  },
+  Builtin {
+    name: "u24_to_i24",
+    bend: "_Oktu_/U24/to_i24",
+    scheme: || mono(arr!(TypeKind::U24 => TypeKind::I24)),
+  },
+  Builtin {
+    name: "u24_to_f24",
+    bend: "_Oktu_/U24/to_f24",
+    scheme: || mono(arr!(TypeKind::U24 => TypeKind::F24)),
+  },
+  Builtin {
+    name: "i24_to_u24",
+    bend: "_Oktu_/I24/to_u24",
+    scheme: || mono(arr!(TypeKind::I24 => TypeKind::U24)),
+  },
+  Builtin {
+    name: "i24_to_f24",
+    bend: "_Oktu_/I24/to_f24",
+    scheme: || mono(arr!(TypeKind::I24 => TypeKind::F24)),
+  },
+  Builtin {
+    name: "f24_to_u24",
+    bend: "_Oktu_/F24/to_u24",
+    scheme: || mono(arr!(TypeKind::F24 => TypeKind::U24)),
+  },
+  Builtin {
+    name: "f24_to_i24",
+    bend: "_Oktu_/F24/to_i24",
+    scheme: || mono(arr!(TypeKind::F24 => TypeKind::I24)),
+  },
+  Builtin {
+    name: "list_concat",
+    bend: "_Oktu_/List/concat",
+    scheme: || poly(1, arr!(list(0) => arr!(list(0) => list(0)))),
+  },
+  Builtin {
+    name: "list_length",
+    bend: "_Oktu_/List/length",
+    scheme: || poly(1, arr!(list(0) => TypeKind::I24)),
+  },
+];
+
+/// Looks up a builtin by its Oktu name.
+pub fn get(name: &str) -> &'static Builtin {
+  BUILTINS
+    .iter()
+    .find(|builtin| builtin.name == name)
+    .unwrap_or_else(|| panic!("unknown builtin '{name}'"))
+}
+
+fn mono(t: impl Into<Type>) -> Scheme {
+  Scheme::new(vec![], t.into())
+}
+
+/// A scheme quantified over `binds` variables, referred to by `var(id)`.
+fn poly(binds: usize, t: impl Into<Type>) -> Scheme {
+  let binds = (0..binds).map(|id| format!("'{id}")).collect();
+  Scheme::new(binds, t.into())
+}
+
+fn var(id: usize) -> Type {
+  Type::new(TypeKind::Generalized { id })
+}
+
+fn list(id: usize) -> Type {
+  TypeKind::list(var(id))
+}
@@ -1,13 +1,21 @@
 pub mod display;
+pub mod index;
 pub mod infer;
+pub mod numeric;
 pub mod types;
 pub mod unification;
+pub mod zonk;
 
-use std::collections::HashMap;
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
-use crate::{ast::Program, elab, report::Reporter};
+use crate::{
+  ast::{Program, Src},
+  builtins::BUILTINS,
+  elab, operators,
+  report::Reporter,
+};
 
-use self::{infer::Infer, types::*};
+use self::{index::Index, infer::Infer, numeric::Numeric, types::*};
 
 #[derive(Clone)]
 pub struct Env {
@@ -16,19 +24,34 @@ pub struct Env {
   pub let_decls: HashMap<String, Scheme>,
   pub enum_decls: HashMap<String, usize>,
   pub variant_to_enum: HashMap<String, String>,
+  /// The name each top level definition in scope has in the linked program.
+  pub link_names: HashMap<String, String>,
+  /// Where the names in scope were defined, here or in the module they
+  /// come from, variants with their `.`.
+  pub defined_at: HashMap<String, Src>,
+  /// The qualified names of the private definitions of imported modules,
+  /// with their module and name.
+  pub private: HashMap<String, (String, String)>,
+  /// The module being checked, `None` for the root module.
+  pub module: Option<String>,
   pub level: usize,
   pub counter: usize,
+  /// Numeric constraints not solved yet, shared by every clone.
+  pub numeric: Rc<RefCell<Vec<Numeric>>>,
+  /// Types and references by source position, shared by every clone.
+  pub index: Rc<RefCell<Index>>,
   pub reporter: Reporter,
 }
 
 impl Env {
   pub fn new(reporter: Reporter) -> Self {
     let mut let_decls = HashMap::new();
+    let mut link_names = HashMap::new();
 
-    let_decls.insert(
-      "print".to_string(),
-      Scheme::new(vec![], TypeKind::print_string()),
-    );
+    for builtin in BUILTINS {
+      let_decls.insert(builtin.name.to_string(), (builtin.scheme)());
+      link_names.insert(builtin.name.to_string(), builtin.bend.to_string());
+    }
 
     Self {
       variables: HashMap::new(),
@@ -36,8 +59,14 @@ impl Env {
       let_decls,
       enum_decls: HashMap::new(),
       variant_to_enum: HashMap::new(),
+      link_names,
+      defined_at: HashMap::new(),
+      private: HashMap::new(),
+      module: None,
       level: 0,
       counter: 0,
+      numeric: Rc::default(),
+      index: Rc::default(),
       reporter,
     }
   }
@@ -79,7 +108,11 @@ impl Env {
     self.level -= 1;
   }
 
+  /// Generalizes the holes of `t` made after the current level, but for
+  /// numeric ones, which are left to the top level definition to default.
   pub fn generalize(&mut self, t: Type) -> Scheme {
+    self.keep_numbers();
+
     let mut counter = 0;
     let level = self.level;
 
@@ -104,6 +137,14 @@ impl Env {
           gen(t2.clone(), level, counter);
         }
 
+        TypeKind::Tuple { elements } => {
+          for element in elements {
+            gen(element.clone(), level, counter);
+          }
+        }
+
+        TypeKind::List { element } => gen(element.clone(), level, counter),
+
         _ => {}
       }
     }
@@ -117,11 +158,47 @@ impl Env {
   pub fn fetch(&self, name: &String) -> Option<&Scheme> {
     self.variables.get(name).or(self.let_decls.get(name))
   }
+
+  /// Records that the code at `src` has type `t`.
+  pub fn record_type(&self, src: &Src, t: &Type) {
+    self.index.borrow_mut().types.push((src.clone(), t.clone()));
+  }
+
+  /// Records that `name`, used at `src`, refers to its definition in scope,
+  /// if it is known where it is.
+  pub fn record_reference(&self, name: &str, src: &Src) {
+    if let Some(definition) = self.defined_at.get(name) {
+      let reference = (src.clone(), definition.clone());
+      self.index.borrow_mut().references.push(reference);
+    }
+  }
+
+  /// Brings the variables bound at `binders` into scope, recording their
+  /// scope `src`.
+  pub fn bind(&mut self, binders: Vec<(String, Src, Scheme)>, src: &Src) {
+    let mut scope = Vec::new();
+    for (name, binder, scheme) in binders {
+      scope.push((name.clone(), scheme.t.clone()));
+      self.defined_at.insert(name.clone(), binder);
+      self.variables.insert(name, scheme);
+    }
+    self.index.borrow_mut().scopes.push((src.clone(), scope));
+  }
+
+  /// The name a definition of the current module gets in the linked program.
+  pub fn link_name(&self, name: &str) -> String {
+    let name = operators::mangle(name);
+    match &self.module {
+      Some(module) => format!("{module}.{name}"),
+      None => name,
+    }
+  }
 }
 
 impl Infer for Program {
-  type Out = elab::Program;
+  type Out = (Env, elab::Program);
 
+  // Returns the env with every top level definition of the program
   fn infer(self, mut env: Env) -> (Self::Out, Type) {
     let mut declarations = Vec::with_capacity(self.declarations.len());
 
@@ -132,10 +209,13 @@ impl Infer for Program {
     }
 
     (
-      elab::Program {
-        file_name: self.file_name,
-        declarations,
-      },
+      (
+        env,
+        elab::Program {
+          file_name: self.file_name,
+          declarations,
+        },
+      ),
       TypeKind::boolean(),
     )
   }
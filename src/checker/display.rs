@@ -1,22 +1,72 @@
 use core::fmt;
+use std::cell::RefCell;
 
 use super::{Hole, HoleKind, TypeKind};
 
+/// A type variable, either still a hole or already generalized.
+#[derive(PartialEq)]
+enum Var {
+  Hole(Hole),
+  Generalized(usize),
+}
+
+/// Names the type variables of the types rendered together, `'a`, `'b`,
+/// ... in the order they first appear, so that different variables never
+/// share a name.
+#[derive(Default)]
+pub struct TypeNames(RefCell<Vec<Var>>);
+
+/// A type rendered with the names of a [`TypeNames`].
+struct Shown<'a> {
+  t: &'a TypeKind,
+  names: &'a TypeNames,
+}
+
+impl TypeNames {
+  pub fn show(&self, t: &TypeKind) -> String {
+    Shown { t, names: self }.to_string()
+  }
+
+  fn name(&self, var: Var) -> String {
+    let mut vars = self.0.borrow_mut();
+    let index = match vars.iter().position(|v| *v == var) {
+      Some(index) => index,
+      None => {
+        vars.push(var);
+        vars.len() - 1
+      }
+    };
+    let letter = (b'a' + (index % 26) as u8) as char;
+    match index / 26 {
+      0 => format!("'{letter}"),
+      n => format!("'{letter}{n}"),
+    }
+  }
+}
+
 impl fmt::Display for TypeKind {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    match self {
+    f.write_str(&TypeNames::default().show(self))
+  }
+}
+
+impl fmt::Display for Shown<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let names = self.names;
+    let shown = |t| Shown { t, names };
+    match self.t {
       TypeKind::Unit => write!(f, "()"),
       TypeKind::Variable { name } => write!(f, "{name}"),
-      TypeKind::Generalized { id } => {
-        let c = std::char::from_u32(*id as u32 + 97).unwrap_or('?');
-        write!(f, "'{c}")
-      }
-      TypeKind::Hole { hole } => write!(f, "{hole}"),
+      TypeKind::Generalized { id } => write!(f, "{}", names.name(Var::Generalized(*id))),
+      TypeKind::Hole { hole } => match hole.get() {
+        HoleKind::Bound { t } => write!(f, "{}", shown(&t)),
+        HoleKind::Unbound { .. } => write!(f, "{}", names.name(Var::Hole(hole.clone()))),
+      },
       TypeKind::Arrow { t1, t2 } => {
         if t1.need_parens() {
-          write!(f, "({t1}) -> {t2}")
+          write!(f, "({}) -> {}", shown(t1), shown(t2))
         } else {
-          write!(f, "{t1} -> {t2}")
+          write!(f, "{} -> {}", shown(t1), shown(t2))
         }
       }
       TypeKind::Enum { name } => write!(f, "{name}"),
@@ -26,29 +76,41 @@ impl fmt::Display for TypeKind {
           "({})",
           elements
             .iter()
-            .map(|e| e.to_string())
+            .map(|e| shown(e).to_string())
             .collect::<Vec<String>>()
             .join(", ")
         )
       }
-      TypeKind::Number => write!(f, "number"),
+      TypeKind::List { element } => {
+        if element.need_parens() || element.is_list() {
+          write!(f, "list ({})", shown(element))
+        } else {
+          write!(f, "list {}", shown(element))
+        }
+      }
+      TypeKind::U24 => write!(f, "u24"),
+      TypeKind::I24 => write!(f, "i24"),
+      TypeKind::F24 => write!(f, "f24"),
       TypeKind::String => write!(f, "string"),
+      TypeKind::Char => write!(f, "char"),
       TypeKind::Boolean => write!(f, "bool"),
       TypeKind::Error => write!(f, "<Error>"),
     }
   }
 }
 
-impl fmt::Display for Hole {
-  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    match self.get() {
-      HoleKind::Bound { t } => write!(f, "{t}"),
-      HoleKind::Unbound { name, level } => write!(f, "^{name}_{level}"),
+impl TypeKind {
+  fn is_list(&self) -> bool {
+    match self {
+      Self::List { .. } => true,
+      Self::Hole { hole } => match hole.get() {
+        HoleKind::Bound { t } => t.is_list(),
+        HoleKind::Unbound { .. } => false,
+      },
+      _ => false,
     }
   }
-}
 
-impl TypeKind {
   fn need_parens(&self) -> bool {
     match self {
       Self::Arrow { .. } => true,
@@ -0,0 +1,25 @@
+use crate::ast::Src;
+
+use super::types::Type;
+
+/// What checking a module found out about the places in its source, for
+/// editors.
+#[derive(Default)]
+pub struct Index {
+  /// The type of every expression and binder.
+  pub types: Vec<(Src, Type)>,
+  /// Every use of a name, with where the name was defined.
+  pub references: Vec<(Src, Src)>,
+  /// The variables each part of the source binds, with their types.
+  pub scopes: Vec<(Src, Vec<(String, Type)>)>,
+}
+
+impl Index {
+  /// The innermost entry of `entries` whose source contains `offset`.
+  pub fn innermost<T>(entries: &[(Src, T)], offset: usize) -> Option<&(Src, T)> {
+    entries
+      .iter()
+      .filter(|(src, _)| src.range.contains(&offset))
+      .min_by_key(|(src, _)| src.range.len())
+  }
+}
@@ -1,102 +1,290 @@
 use crate::{
   arr,
   ast::{self, Expression, ExpressionType, Src},
-  checker::{unification::unify, Env, Scheme, Type, TypeKind},
+  checker::{
+    numeric::Origin,
+    unification::{unify, Reason},
+    Env, Scheme, Type, TypeKind,
+  },
   elab,
+  module::ModuleError,
   report::Diagnostic,
 };
 
-use super::Infer;
+use super::{literal::check_literal, Infer};
 
 enum ExpressionInferError {
   UnboundVariable(String, Src),
   UnknownVariant(String, Src),
+  NotAFunction(Option<String>, String, Src),
+  TooManyArguments {
+    function: Option<String>,
+    function_type: String,
+    expected: usize,
+    given: usize,
+    src: Src,
+  },
+  NotConcatenable(String, Src),
+  RefutableParameter(Src),
+  RefutableBinding(Src),
+  /// `t.index` of something that isn't known to be a tuple that long.
+  BadProjection {
+    index: usize,
+    t: String,
+    known: bool,
+    src: Src,
+  },
 }
 
 impl Infer for Expression {
   type Out = elab::Expression;
 
-  fn infer(self, mut env: Env) -> (Self::Out, Type) {
+  fn infer(self, env: Env) -> (Self::Out, Type) {
+    let src = self.src();
+    let index = env.index.clone();
+    let (data, t) = self.infer_unrecorded(env);
+    index.borrow_mut().types.push((src.clone(), t.clone()));
+    (elab::Expression::new(data, t.clone(), src), t)
+  }
+}
+
+impl Expression {
+  fn infer_unrecorded(self, mut env: Env) -> (elab::ExpressionType, Type) {
     match *self.data {
-      ExpressionType::Unit => (elab::Expression::Unit, Type::new(TypeKind::Unit)),
+      ExpressionType::Unit => (elab::ExpressionType::Unit, Type::new(TypeKind::Unit)),
       ExpressionType::Hole { name } => (
-        elab::Expression::Hole { name: name.clone() },
+        elab::ExpressionType::Hole { name: name.clone() },
         env.new_hole_named(name),
       ),
       ExpressionType::Variable { name } => match env.fetch(&name) {
-        Some(scheme) => (
-          elab::Expression::Variable { name },
-          env.instantiate(scheme.clone()),
-        ),
+        Some(scheme) => {
+          let t = env.instantiate(scheme.clone());
+          env.record_reference(&name, &self.src);
+          let link_name = match env.link_names.get(&name) {
+            Some(link_name) if !env.variables.contains_key(&name) => link_name.clone(),
+            _ => name.clone(),
+          };
+          let variable = elab::ExpressionType::Variable {
+            name: link_name,
+            source: name,
+          };
+          (variable, t)
+        }
         None => {
-          env.reporter.report(ExpressionInferError::UnboundVariable(
-            name.clone(),
-            self.src,
-          ));
+          match env.private.get(&name) {
+            Some((module, item)) => {
+              env
+                .reporter
+                .report(ModuleError::Private(module.clone(), item.clone(), self.src))
+            }
+            None => env.reporter.report(ExpressionInferError::UnboundVariable(
+              name.clone(),
+              self.src,
+            )),
+          }
           (
-            elab::Expression::error(format!("Unbound variable '{name}'.")),
+            elab::ExpressionType::error(format!("Unbound variable '{name}'.")),
             Type::new(TypeKind::Error),
           )
         }
       },
-      ExpressionType::Fun { variable, body } => {
-        let hole = env.new_hole();
-        let scheme = Scheme::new(vec![], hole.clone());
-
+      ExpressionType::Fun { parameters, body } => {
         let mut new_env = env.clone();
-        new_env.variables.insert(variable.clone(), scheme);
+        let mut binders = Vec::new();
+        let mut elab_parameters = Vec::new();
+        let mut parameter_types = Vec::new();
+        for parameter in parameters {
+          if parameter.data.is_refutable() {
+            env
+              .reporter
+              .report(ExpressionInferError::RefutableParameter(parameter.src()));
+          }
+          let ((binds, elab_parameter), parameter_type) = parameter.infer(env.clone());
+          for (name, (src, t)) in binds {
+            binders.push((name, src, Scheme::new(vec![], t)));
+          }
+          elab_parameters.push(elab_parameter);
+          parameter_types.push(parameter_type);
+        }
+        new_env.bind(binders, &body.src);
 
         let (elab_body, body_type) = body.infer(new_env);
-        let arrow = Type::new(arr!(hole => body_type));
-
-        (
-          elab::Expression::Fun {
-            variable,
-            body: Box::new(elab_body),
+        let (elab_fun, arrow) = elab_parameters.into_iter().zip(parameter_types).rfold(
+          (elab_body, body_type),
+          |(body, body_type), (parameter, t)| {
+            let t: Type = arr!(t => body_type).into();
+            let fun = elab::ExpressionType::Fun {
+              parameter,
+              body: Box::new(body),
+            };
+            (elab::Expression::new(fun, t.clone(), self.src.clone()), t)
           },
-          arrow,
-        )
+        );
+
+        (*elab_fun.data, arrow)
       }
       ExpressionType::Application { function, argument } => {
-        let (elab_function, function_type) = function.infer(env.clone());
-        let (elab_argument, argument_type) = argument.infer(env.clone());
+        let mut arguments = vec![*argument];
+        let mut function = *function;
+        loop {
+          match *function.data {
+            ExpressionType::Application {
+              function: inner,
+              argument,
+            } => {
+              arguments.push(*argument);
+              function = *inner;
+            }
+            data => {
+              function.data = Box::new(data);
+              break;
+            }
+          }
+        }
+        arguments.reverse();
 
-        let hole = env.new_hole();
+        let function_name = match &*function.data {
+          ExpressionType::Variable { name } => Some(name.clone()),
+          _ => None,
+        };
+        let function_src = function.src();
+        let given = arguments.len();
+        let (mut elab_function, full_type) = function.infer(env.clone());
 
-        let arrow_type: Type = arr!(argument_type => hole.clone()).into();
+        let mut function_type = full_type.clone();
+        let mut reported = false;
+        for (index, argument) in arguments.into_iter().enumerate() {
+          let argument_src = argument.src();
+          let application_src = Src::new(
+            self.src.file,
+            function_src.range.start,
+            argument_src.range.end,
+          );
+          let (elab_argument, argument_type) = argument.infer(env.clone());
 
-        unify(&env, function_type, arrow_type.clone(), self.src);
-        (
-          elab::Expression::Application {
+          let resolved = function_type.resolve();
+          // A number is never a function, so it is taken as the `i24` it
+          // would default to and reported as not being one.
+          if let TypeKind::Hole { hole } = &*resolved {
+            if env.is_pending_numeric(&resolved) {
+              hole.fill(TypeKind::i24());
+            }
+          }
+          let resolved = resolved.resolve();
+          function_type = match &*resolved {
+            TypeKind::Arrow { t1, t2 } => {
+              let reason = Reason::Argument {
+                function: function_name.clone(),
+                index: index + 1,
+                function_src: function_src.clone(),
+              };
+              unify(&env, t1.clone(), argument_type, argument_src, reason);
+              t2.clone()
+            }
+            TypeKind::Hole { .. } => {
+              let hole = env.new_hole();
+              let arrow_type: Type = arr!(argument_type => hole.clone()).into();
+              unify(&env, resolved, arrow_type, self.src.clone(), Reason::None);
+              hole
+            }
+            TypeKind::Error => resolved,
+            _ => {
+              if !reported {
+                let error = if index == 0 {
+                  ExpressionInferError::NotAFunction(
+                    function_name.clone(),
+                    resolved.to_string(),
+                    self.src.clone(),
+                  )
+                } else {
+                  ExpressionInferError::TooManyArguments {
+                    function: function_name.clone(),
+                    function_type: full_type.to_string(),
+                    expected: index,
+                    given,
+                    src: self.src.clone(),
+                  }
+                };
+                env.reporter.report(error);
+                reported = true;
+              }
+              Type::new(TypeKind::Error)
+            }
+          };
+
+          let application = elab::ExpressionType::Application {
             function: Box::new(elab_function),
             argument: Box::new(elab_argument),
-          },
-          hole,
-        )
+          };
+          elab_function =
+            elab::Expression::new(application, function_type.clone(), application_src);
+        }
+
+        (*elab_function.data, function_type)
       }
       ExpressionType::Literal { literal } => {
-        let (elab_literal, literal_type) = literal.infer(env);
+        let (elab_literal, literal_type) = literal.infer(env.clone());
+        check_literal(&mut env, &elab_literal, self.src);
         (
-          elab::Expression::Literal {
+          elab::ExpressionType::Literal {
             literal: elab_literal,
           },
           literal_type,
         )
       }
+      ExpressionType::Interpolation { parts } => {
+        let mut elab_parts = Vec::new();
+        for part in parts {
+          let part_src = part.src();
+          let (elab_part, t) = part.infer(env.clone());
+          unify(&env, TypeKind::string(), t, part_src, Reason::Interpolation);
+          elab_parts.push(elab_part);
+        }
+        let concat = elab_parts
+          .into_iter()
+          .rev()
+          .reduce(|rest, part| {
+            let src = Src::new(self.src.file, part.src.range.start, rest.src.range.end);
+            let concat = elab::ExpressionType::BinaryOp {
+              op: elab::Operation::Concat,
+              lhs: Box::new(part),
+              rhs: Box::new(rest),
+            };
+            elab::Expression::new(concat, TypeKind::string(), src)
+          })
+          .map(|concat| *concat.data)
+          .unwrap_or(elab::ExpressionType::Literal {
+            literal: elab::Literal::String {
+              value: String::new(),
+            },
+          });
+        (concat, TypeKind::string())
+      }
       ExpressionType::Let { bind, value, next } => {
+        if bind.data.is_refutable() {
+          env
+            .reporter
+            .report(ExpressionInferError::RefutableBinding(bind.src()));
+        }
+        let bind_src = bind.src();
+
         env.enter_level();
         let (elab_value, value_type) = value.infer(env.clone());
+        let ((binds, elab_bind), bind_type) = bind.infer(env.clone());
+        unify(&env, bind_type, value_type, bind_src, Reason::None);
         env.leave_level();
 
-        let value_g = env.generalize(value_type);
-
         let mut new_env = env.clone();
-        new_env.variables.insert(bind.clone(), value_g);
+        let binders = binds
+          .into_iter()
+          .map(|(name, (src, t))| (name, src, env.generalize(t)))
+          .collect();
+        new_env.bind(binders, &next.src);
 
         let (elab_next, next_type) = next.infer(new_env);
         (
-          elab::Expression::Let {
-            bind,
+          elab::ExpressionType::Let {
+            bind: elab_bind,
             value: Box::new(elab_value),
             next: Box::new(elab_next),
           },
@@ -112,18 +300,36 @@ impl Infer for Expression {
         let then_src = then.src();
         let otherwise_src = otherwise.src();
         let (elab_condition, condition_type) = condition.infer(env.clone());
-        unify(&env, condition_type, TypeKind::boolean(), condition_src);
+        unify(
+          &env,
+          TypeKind::boolean(),
+          condition_type,
+          condition_src,
+          Reason::Condition,
+        );
 
         let return_type = env.new_hole();
 
         let (elab_then, then_type) = then.infer(env.clone());
-        unify(&env, return_type.clone(), then_type, then_src);
+        unify(
+          &env,
+          return_type.clone(),
+          then_type,
+          then_src.clone(),
+          Reason::None,
+        );
 
         let (elab_otherwise, otherwise_type) = otherwise.infer(env.clone());
-        unify(&env, return_type.clone(), otherwise_type, otherwise_src);
+        unify(
+          &env,
+          return_type.clone(),
+          otherwise_type,
+          otherwise_src,
+          Reason::Branches { then: then_src },
+        );
 
         (
-          elab::Expression::If {
+          elab::ExpressionType::If {
             condition: Box::new(elab_condition),
             then: Box::new(elab_then),
             otherwise: Box::new(elab_otherwise),
@@ -134,21 +340,41 @@ impl Infer for Expression {
       ExpressionType::Match { scrutinee, arms } => {
         let return_type = env.new_hole();
 
+        let scrutinee_src = scrutinee.src();
         let (elab_scrutinee, scrutinee_type) = scrutinee.infer(env.clone());
         let mut elab_arms = Vec::new();
+        let mut first_src: Option<Src> = None;
 
         for ast::Arm { left, right } in arms {
           let left_src = left.src();
           let ((binds, elab_left), left_type) = left.infer(env.clone());
-          for (bind, value) in binds {
-            env.variables.insert(bind, Scheme::new(vec![], value));
-          }
+          let mut arm_env = env.clone();
+          let binders = binds
+            .into_iter()
+            .map(|(name, (src, t))| (name, src, Scheme::new(vec![], t)))
+            .collect();
+          arm_env.bind(binders, &right.src);
 
           let right_src = right.src();
-          let (elab_right, right_type) = right.infer(env.clone());
+          let (elab_right, right_type) = right.infer(arm_env);
 
-          unify(&env, scrutinee_type.clone(), left_type, left_src);
-          unify(&env, return_type.clone(), right_type, right_src);
+          unify(
+            &env,
+            scrutinee_type.clone(),
+            left_type,
+            left_src,
+            Reason::Pattern {
+              scrutinee: scrutinee_src.clone(),
+            },
+          );
+          let reason = match &first_src {
+            Some(first) => Reason::Arms {
+              first: first.clone(),
+            },
+            None => Reason::None,
+          };
+          first_src.get_or_insert(right_src.clone());
+          unify(&env, return_type.clone(), right_type, right_src, reason);
 
           elab_arms.push(elab::Arm {
             left: elab_left,
@@ -157,7 +383,7 @@ impl Infer for Expression {
         }
 
         (
-          elab::Expression::Match {
+          elab::ExpressionType::Match {
             scrutinee: Box::new(elab_scrutinee),
             arms: elab_arms,
           },
@@ -166,16 +392,88 @@ impl Infer for Expression {
       }
       ExpressionType::BinaryOp { op, lhs, rhs } => {
         let (elab_op, op_type) = op.infer(env.clone());
+        let TypeKind::Arrow {
+          t1: lhs_expected,
+          t2: rest,
+        } = &*op_type
+        else {
+          unreachable!("operators are binary functions")
+        };
+        let TypeKind::Arrow {
+          t1: rhs_expected,
+          t2: ret_type,
+        } = &**rest
+        else {
+          unreachable!("operators are binary functions")
+        };
 
+        let lhs_src = lhs.src();
+        let rhs_src = rhs.src();
         let (elab_lhs, lhs_type) = lhs.infer(env.clone());
         let (elab_rhs, rhs_type) = rhs.infer(env.clone());
 
-        let ret_type = env.new_hole();
-        let to_unify: Type = arr!(lhs_type => arr!(rhs_type => ret_type.clone())).into();
+        let op = elab_op.to_string();
+        let origin = if elab_op.is_bitwise() {
+          Some(Origin::BitOperand { op: op.clone() })
+        } else if elab_op.is_comparison() {
+          Some(Origin::Comparison { op: op.clone() })
+        } else if elab_op.is_numeric() {
+          Some(Origin::Operand { op: op.clone() })
+        } else {
+          None
+        };
+
+        // An operand that can't be a number is reported on its own, rather
+        // than as a mismatch with the other one.
+        let reason = Reason::Operand { op };
+        let operands = [
+          (lhs_expected, lhs_type, lhs_src),
+          (rhs_expected, rhs_type, rhs_src),
+        ];
+        for (expected, t, src) in operands {
+          let fits = match &origin {
+            Some(origin) => env.check_operand(&t, origin, &src),
+            None => true,
+          };
+          if fits {
+            unify(&env, expected.clone(), t, src, reason.clone());
+          }
+        }
+        if let Some(origin) = origin {
+          env.constrain_numeric(lhs_expected.clone(), origin, self.src.clone());
+        }
+
+        let elab_op = match elab_op {
+          elab::Operation::Concat => {
+            let operand_type = lhs_expected.clone().resolve();
+            match &*operand_type {
+              TypeKind::List { .. } => elab::Operation::ConcatList,
+              TypeKind::String | TypeKind::Error => elab::Operation::Concat,
+              TypeKind::Hole { .. } => {
+                unify(
+                  &env,
+                  TypeKind::string(),
+                  operand_type,
+                  self.src.clone(),
+                  Reason::None,
+                );
+                elab::Operation::Concat
+              }
+              _ => {
+                env.reporter.report(ExpressionInferError::NotConcatenable(
+                  operand_type.to_string(),
+                  self.src.clone(),
+                ));
+                elab::Operation::Concat
+              }
+            }
+          }
+          elab_op => elab_op,
+        };
 
-        unify(&env, op_type, to_unify, self.src);
+        let ret_type = ret_type.clone();
         (
-          elab::Expression::BinaryOp {
+          elab::ExpressionType::BinaryOp {
             op: elab_op,
             lhs: Box::new(elab_lhs),
             rhs: Box::new(elab_rhs),
@@ -183,27 +481,79 @@ impl Infer for Expression {
           ret_type,
         )
       }
+      ExpressionType::UnaryOp { op, operand } => {
+        let (elab_op, op_type) = op.infer(env.clone());
+        let TypeKind::Arrow {
+          t1: expected,
+          t2: ret_type,
+        } = &*op_type
+        else {
+          unreachable!("unary operators are functions")
+        };
+
+        let operand_src = operand.src();
+        let (elab_operand, operand_type) = operand.infer(env.clone());
+
+        let op = elab_op.to_string().trim().to_string();
+        unify(
+          &env,
+          expected.clone(),
+          operand_type,
+          operand_src,
+          Reason::Operand { op },
+        );
+        if let elab::UnaryOperation::Neg { .. } = elab_op {
+          env.constrain_numeric(expected.clone(), Origin::Negation, self.src.clone());
+        }
+
+        (
+          elab::ExpressionType::UnaryOp {
+            op: elab_op,
+            operand: Box::new(elab_operand),
+          },
+          ret_type.clone(),
+        )
+      }
       ExpressionType::Variant { variant } => match env.variant_to_enum.get(&variant) {
-        Some(name) => (
-          elab::Expression::Variant { variant },
-          Type::new(TypeKind::Enum { name: name.clone() }),
-        ),
+        Some(name) => {
+          env.record_reference(&format!(".{variant}"), &self.src);
+          (
+            elab::ExpressionType::Variant { variant },
+            Type::new(TypeKind::Enum { name: name.clone() }),
+          )
+        }
         None => {
           env.reporter.report(ExpressionInferError::UnknownVariant(
             variant.clone(),
             self.src,
           ));
           (
-            elab::Expression::error(format!("Unknown variant '{variant}'.")),
+            elab::ExpressionType::error(format!("Unknown variant '{variant}'.")),
             Type::new(TypeKind::Error),
           )
         }
       },
+      ExpressionType::List { elements } => {
+        let element_type = env.new_hole();
+        let mut elab_elements = Vec::new();
+        for element in elements {
+          let element_src = element.src();
+          let (elab_element, t) = element.infer(env.clone());
+          unify(&env, element_type.clone(), t, element_src, Reason::None);
+          elab_elements.push(elab_element);
+        }
+        (
+          elab::ExpressionType::List {
+            elements: elab_elements,
+          },
+          TypeKind::list(element_type),
+        )
+      }
       ExpressionType::Tuple { elements } => {
         let (elab_elements, elements_type) =
           elements.into_iter().map(|e| e.infer(env.clone())).unzip();
         (
-          elab::Expression::Tuple {
+          elab::ExpressionType::Tuple {
             elements: elab_elements,
           },
           Type::new(TypeKind::Tuple {
@@ -211,6 +561,39 @@ impl Infer for Expression {
           }),
         )
       }
+      ExpressionType::Projection { tuple, index } => {
+        let (elab_tuple, tuple_type) = tuple.infer(env.clone());
+        let resolved = tuple_type.resolve();
+        match &*resolved {
+          TypeKind::Tuple { elements } if index < elements.len() => (
+            elab::ExpressionType::Projection {
+              tuple: Box::new(elab_tuple),
+              index,
+              size: elements.len(),
+            },
+            elements[index].clone(),
+          ),
+          TypeKind::Error => (
+            elab::ExpressionType::error("Projection of an error.".to_string()),
+            resolved,
+          ),
+          _ => {
+            env.reporter.report(ExpressionInferError::BadProjection {
+              index,
+              t: resolved.to_string(),
+              known: !matches!(&*resolved, TypeKind::Hole { .. }),
+              src: self.src,
+            });
+            (
+              elab::ExpressionType::error(format!("No element {index}.")),
+              Type::new(TypeKind::Error),
+            )
+          }
+        }
+      }
+      ExpressionType::Infix { .. } | ExpressionType::Section { .. } => {
+        unreachable!("operators are resolved before type checking")
+      }
     }
   }
 }
@@ -220,6 +603,42 @@ impl Diagnostic for ExpressionInferError {
     match self {
       ExpressionInferError::UnboundVariable(name, _) => format!("Unbound variable '{name}'."),
       ExpressionInferError::UnknownVariant(variant, _) => format!("Unknown variant '{variant}'."),
+      ExpressionInferError::NotAFunction(Some(name), ..) => format!("`{name}` is not a function."),
+      ExpressionInferError::NotAFunction(None, ..) => {
+        "This expression is not a function.".to_string()
+      }
+      ExpressionInferError::TooManyArguments {
+        function,
+        expected,
+        given,
+        ..
+      } => {
+        let function = match function {
+          Some(name) => format!("`{name}`"),
+          None => "This function".to_string(),
+        };
+        let arguments = if *expected == 1 {
+          "argument"
+        } else {
+          "arguments"
+        };
+        format!("{function} takes {expected} {arguments} but {given} were given.")
+      }
+      ExpressionInferError::NotConcatenable(..) => {
+        "Only strings and lists can be concatenated.".to_string()
+      }
+      ExpressionInferError::RefutableParameter(_) => {
+        "Function parameters must match every value.".to_string()
+      }
+      ExpressionInferError::RefutableBinding(_) => {
+        "Patterns in `let` must match every value.".to_string()
+      }
+      ExpressionInferError::BadProjection { known: false, .. } => {
+        "Cannot project out of a value of unknown type.".to_string()
+      }
+      ExpressionInferError::BadProjection { index, .. } => {
+        format!("This value has no element {index}.")
+      }
     }
   }
 
@@ -227,14 +646,59 @@ impl Diagnostic for ExpressionInferError {
     crate::report::Severity::Error
   }
 
+  fn code(&self) -> &'static str {
+    match self {
+      ExpressionInferError::UnboundVariable(..) => "unbound-variable",
+      ExpressionInferError::UnknownVariant(..) => "unknown-variant",
+      ExpressionInferError::NotAFunction(..) => "not-a-function",
+      ExpressionInferError::TooManyArguments { .. } => "too-many-arguments",
+      ExpressionInferError::NotConcatenable(..) => "not-concatenable",
+      ExpressionInferError::RefutableParameter(..) => "refutable-pattern",
+      ExpressionInferError::RefutableBinding(..) => "refutable-pattern",
+      ExpressionInferError::BadProjection { .. } => "bad-projection",
+    }
+  }
+
   fn extra(&self) -> Vec<String> {
-    vec![]
+    match self {
+      ExpressionInferError::NotAFunction(_, t, _) => {
+        vec![format!(
+          "It has type {t} and cannot be applied to arguments."
+        )]
+      }
+      ExpressionInferError::TooManyArguments { function_type, .. } => {
+        vec![format!("Its type is {function_type}.")]
+      }
+      ExpressionInferError::NotConcatenable(t, _) => {
+        vec![format!("The operands have type {t}.")]
+      }
+      ExpressionInferError::RefutableParameter(_) => {
+        vec!["Match on the parameter in the body instead.".to_string()]
+      }
+      ExpressionInferError::RefutableBinding(_) => {
+        vec!["Use a `match` to handle the other cases.".to_string()]
+      }
+      ExpressionInferError::BadProjection { known: false, .. } => {
+        vec![
+          "Its type must already be known to be a tuple, destructure it with `let` instead."
+            .to_string(),
+        ]
+      }
+      ExpressionInferError::BadProjection { t, .. } => vec![format!("It has type {t}.")],
+      _ => vec![],
+    }
   }
 
   fn src(&self) -> Option<Src> {
     match self {
       ExpressionInferError::UnboundVariable(_, src) => Some(src.clone()),
       ExpressionInferError::UnknownVariant(_, src) => Some(src.clone()),
+      ExpressionInferError::NotAFunction(_, _, src) => Some(src.clone()),
+      ExpressionInferError::TooManyArguments { src, .. } => Some(src.clone()),
+      ExpressionInferError::NotConcatenable(_, src) => Some(src.clone()),
+      ExpressionInferError::RefutableParameter(src) => Some(src.clone()),
+      ExpressionInferError::RefutableBinding(src) => Some(src.clone()),
+      ExpressionInferError::BadProjection { src, .. } => Some(src.clone()),
     }
   }
 }
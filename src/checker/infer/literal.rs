@@ -1,6 +1,6 @@
 use crate::{
-  ast::Literal,
-  checker::{Env, Type, TypeKind},
+  ast::{Literal, Src},
+  checker::{numeric::Origin, Env, Type, TypeKind},
   elab,
 };
 
@@ -9,11 +9,35 @@ use super::Infer;
 impl Infer for Literal {
   type Out = elab::Literal;
 
-  fn infer(self, _: Env) -> (Self::Out, Type) {
+  // Integer literals get a fresh type, constrained to be numeric by
+  // `check_literal`.
+  fn infer(self, mut env: Env) -> (Self::Out, Type) {
     match self {
-      Literal::Number { value } => (elab::Literal::Number { value }, TypeKind::number()),
+      Literal::Integer { value } => {
+        let t = env.new_hole();
+        (
+          elab::Literal::Integer {
+            value,
+            t: t.clone(),
+          },
+          t,
+        )
+      }
+      Literal::Float { value } => (elab::Literal::Float { value }, TypeKind::f24()),
       Literal::String { value } => (elab::Literal::String { value }, TypeKind::string()),
+      Literal::Char { value } => (elab::Literal::Char { value }, TypeKind::char()),
       Literal::Boolean { value } => (elab::Literal::Boolean { value }, TypeKind::boolean()),
     }
   }
 }
+
+/// Checks that a number literal at `src` fits in its type.
+pub fn check_literal(env: &mut Env, literal: &elab::Literal, src: Src) {
+  match literal {
+    elab::Literal::Integer { value, t } => {
+      env.constrain_numeric(t.clone(), Origin::Literal { value: *value }, src)
+    }
+    elab::Literal::Float { value } if value.is_infinite() => env.float_out_of_range(src),
+    _ => {}
+  }
+}
@@ -1,5 +1,6 @@
 use crate::{
-  ast::Operation,
+  arr,
+  ast::{Operation, UnaryOperation},
   checker::{Env, Type, TypeKind},
   elab,
 };
@@ -9,19 +10,84 @@ use super::Infer;
 impl Infer for Operation {
   type Out = elab::Operation;
 
-  fn infer(self, _: Env) -> (Self::Out, Type) {
+  fn infer(self, mut env: Env) -> (Self::Out, Type) {
+    // Arithmetic and comparisons take any numeric type, which the caller
+    // constrains once the operands are known.
     match self {
-      Operation::Add => (elab::Operation::Add, TypeKind::num_num()),
-      Operation::Sub => (elab::Operation::Sub, TypeKind::num_num()),
-      Operation::Mul => (elab::Operation::Mul, TypeKind::num_num()),
-      Operation::Div => (elab::Operation::Div, TypeKind::num_num()),
-      Operation::Gt => (elab::Operation::Gt, TypeKind::num_logical()),
-      Operation::Gte => (elab::Operation::Gte, TypeKind::num_logical()),
-      Operation::Lt => (elab::Operation::Lt, TypeKind::num_logical()),
-      Operation::Lte => (elab::Operation::Lte, TypeKind::num_logical()),
-      Operation::Eq => (elab::Operation::Eq, TypeKind::num_logical()),
-      Operation::Neq => (elab::Operation::Neq, TypeKind::num_logical()),
-      Operation::Concat => (elab::Operation::Concat, TypeKind::str_str()),
+      Operation::Add => (elab::Operation::Add, TypeKind::same_same(env.new_hole())),
+      Operation::Sub => (elab::Operation::Sub, TypeKind::same_same(env.new_hole())),
+      Operation::Mul => (elab::Operation::Mul, TypeKind::same_same(env.new_hole())),
+      Operation::Div => (elab::Operation::Div, TypeKind::same_same(env.new_hole())),
+      Operation::Rem => (elab::Operation::Rem, TypeKind::same_same(env.new_hole())),
+      Operation::Pow => {
+        let t = env.new_hole();
+        (
+          elab::Operation::Pow { t: t.clone() },
+          TypeKind::same_same(t),
+        )
+      }
+      Operation::BitAnd => (elab::Operation::BitAnd, TypeKind::same_same(env.new_hole())),
+      Operation::BitOr => (elab::Operation::BitOr, TypeKind::same_same(env.new_hole())),
+      Operation::BitXor => (elab::Operation::BitXor, TypeKind::same_same(env.new_hole())),
+      Operation::Shl => {
+        let t = env.new_hole();
+        (
+          elab::Operation::Shl { t: t.clone() },
+          TypeKind::same_same(t),
+        )
+      }
+      Operation::Shr => {
+        let t = env.new_hole();
+        (
+          elab::Operation::Shr { t: t.clone() },
+          TypeKind::same_same(t),
+        )
+      }
+      Operation::Gt => (elab::Operation::Gt, TypeKind::same_logical(env.new_hole())),
+      Operation::Gte => (elab::Operation::Gte, TypeKind::same_logical(env.new_hole())),
+      Operation::Lt => (elab::Operation::Lt, TypeKind::same_logical(env.new_hole())),
+      Operation::Lte => (elab::Operation::Lte, TypeKind::same_logical(env.new_hole())),
+      Operation::Eq => (elab::Operation::Eq, TypeKind::same_logical(env.new_hole())),
+      Operation::Neq => (elab::Operation::Neq, TypeKind::same_logical(env.new_hole())),
+      // Strings or lists, decided once the operands are known.
+      Operation::Concat => (elab::Operation::Concat, TypeKind::same_same(env.new_hole())),
+      Operation::And => (
+        elab::Operation::And,
+        TypeKind::same_same(TypeKind::boolean()),
+      ),
+      Operation::Or => (
+        elab::Operation::Or,
+        TypeKind::same_same(TypeKind::boolean()),
+      ),
+      Operation::Cons => {
+        let a = env.new_hole();
+        let list = TypeKind::list(a.clone());
+        (
+          elab::Operation::Cons,
+          arr!(a => arr!(list.clone() => list)).into(),
+        )
+      }
+    }
+  }
+}
+
+impl Infer for UnaryOperation {
+  type Out = elab::UnaryOperation;
+
+  // Negation takes any numeric type, constrained by the caller.
+  fn infer(self, mut env: Env) -> (Self::Out, Type) {
+    match self {
+      UnaryOperation::Neg => {
+        let t = env.new_hole();
+        (
+          elab::UnaryOperation::Neg { t: t.clone() },
+          arr!(t.clone() => t).into(),
+        )
+      }
+      UnaryOperation::Not => (
+        elab::UnaryOperation::Not,
+        arr!(TypeKind::Boolean => TypeKind::Boolean).into(),
+      ),
     }
   }
 }
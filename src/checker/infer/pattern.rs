@@ -2,39 +2,59 @@ use std::collections::HashMap;
 
 use crate::{
   ast::{Pattern, PatternType, Src},
-  checker::{Env, Type, TypeKind},
+  checker::{
+    unification::{unify, Reason},
+    Env, Type, TypeKind,
+  },
   elab,
   report::Diagnostic,
 };
 
-use super::Infer;
+use super::{literal::check_literal, Infer};
 
 enum PatternInferError {
   UnknownVariant(String, Src),
 }
 
+/// The variables a pattern binds, with where they are written and their
+/// types.
+pub type Binds = HashMap<String, (Src, Type)>;
+
 impl Infer for Pattern {
-  type Out = (HashMap<String, Type>, elab::Pattern);
+  type Out = (Binds, elab::Pattern);
+
+  fn infer(self, env: Env) -> (Self::Out, Type) {
+    let src = self.src();
+    let index = env.index.clone();
+    let ((binds, data), t) = self.infer_unrecorded(env);
+    index.borrow_mut().types.push((src.clone(), t.clone()));
+    ((binds, elab::Pattern::new(data, t.clone(), src)), t)
+  }
+}
 
-  fn infer(self, mut env: Env) -> (Self::Out, Type) {
+impl Pattern {
+  fn infer_unrecorded(self, mut env: Env) -> ((Binds, elab::PatternType), Type) {
     let mut map = HashMap::new();
     match *self.data {
       PatternType::Variable { name } => {
-        if name.starts_with('_') {
-          ((map, elab::Pattern::Wildcard), env.new_hole())
+        if name == "_" {
+          ((map, elab::PatternType::Wildcard), env.new_hole())
         } else {
           let hole = env.new_hole();
-          map.insert(name.clone(), hole.clone());
-          ((map, elab::Pattern::Variable { name }), hole)
+          map.insert(name.clone(), (self.src, hole.clone()));
+          ((map, elab::PatternType::Variable { name }), hole)
         }
       }
       PatternType::Variant { variant } => match env.variant_to_enum.get(&variant) {
-        Some(enum_name) => (
-          (map, elab::Pattern::Variant { variant }),
-          Type::new(TypeKind::Enum {
-            name: enum_name.clone(),
-          }),
-        ),
+        Some(enum_name) => {
+          env.record_reference(&format!(".{variant}"), &self.src);
+          (
+            (map, elab::PatternType::Variant { variant }),
+            Type::new(TypeKind::Enum {
+              name: enum_name.clone(),
+            }),
+          )
+        }
         None => {
           env
             .reporter
@@ -42,38 +62,67 @@ impl Infer for Pattern {
           (
             (
               map,
-              elab::Pattern::error(format!("Unknown variant '{variant}'.")),
+              elab::PatternType::error(format!("Unknown variant '{variant}'.")),
             ),
             Type::new(TypeKind::Error),
           )
         }
       },
       PatternType::Literal { literal } => {
-        let (elab_literal, literal_type) = literal.infer(env);
+        let (elab_literal, literal_type) = literal.infer(env.clone());
+        check_literal(&mut env, &elab_literal, self.src);
         (
           (
             map,
-            elab::Pattern::Literal {
+            elab::PatternType::Literal {
               literal: elab_literal,
             },
           ),
           literal_type,
         )
       }
-      PatternType::Tuple { binds } => {
-        let mut elab_binds = Vec::new();
-        let mut elements = Vec::new();
+      PatternType::Tuple { elements } => {
+        let mut elab_elements = Vec::new();
+        let mut types = Vec::new();
 
-        for bind in binds.iter() {
-          let hole = env.new_hole();
-          elab_binds.push(bind.clone());
-          map.insert(bind.clone(), hole.clone());
-          elements.push(hole);
+        for element in elements {
+          let ((binds, elab_element), t) = element.infer(env.clone());
+          map.extend(binds);
+          elab_elements.push(elab_element);
+          types.push(t);
         }
 
         (
-          (map, elab::Pattern::Tuple { binds: elab_binds }),
-          Type::new(TypeKind::Tuple { elements }),
+          (
+            map,
+            elab::PatternType::Tuple {
+              elements: elab_elements,
+            },
+          ),
+          Type::new(TypeKind::Tuple { elements: types }),
+        )
+      }
+      PatternType::Nil => (
+        (map, elab::PatternType::Nil),
+        TypeKind::list(env.new_hole()),
+      ),
+      PatternType::Cons { head, tail } => {
+        let tail_src = tail.src();
+        let ((head_binds, elab_head), head_type) = head.infer(env.clone());
+        let ((tail_binds, elab_tail), tail_type) = tail.infer(env.clone());
+        let list_type = TypeKind::list(head_type);
+        unify(&env, list_type.clone(), tail_type, tail_src, Reason::None);
+        map.extend(head_binds);
+        map.extend(tail_binds);
+        (
+          (
+            map,
+            elab::PatternType::Cons {
+              head: Box::new(elab_head),
+              tail: Box::new(elab_tail),
+            },
+          ),
+          list_type,
         )
       }
     }
@@ -91,6 +140,10 @@ impl Diagnostic for PatternInferError {
     crate::report::Severity::Error
   }
 
+  fn code(&self) -> &'static str {
+    "unknown-variant"
+  }
+
   fn extra(&self) -> Vec<String> {
     vec![]
   }
@@ -1,7 +1,10 @@
 use crate::{
   arr,
   ast::{Enum, Function, TopLevel},
-  checker::{Env, Scheme, Type, TypeKind},
+  checker::{
+    unification::{unify, Reason},
+    Env, Scheme, Type, TypeKind,
+  },
   elab,
 };
 
@@ -14,62 +17,122 @@ impl Infer for TopLevel {
   fn infer(self, mut env: Env) -> (Self::Out, Type) {
     match self {
       TopLevel::Function(Function {
+        public,
         name: function_name,
         rec,
         parameters,
         body,
+        src,
       }) => {
+        let link_name = env.link_name(&function_name);
+
         let mut new_env = env.clone();
-        if rec {
-          let rec_hole = new_env.new_hole();
-          new_env
-            .let_decls
-            .insert(function_name.clone(), Scheme::new(vec![], rec_hole));
-        }
-        let mut parameter_types = Vec::new();
 
         new_env.enter_level();
-        for param in parameters.iter() {
-          let hole = new_env.new_hole();
+        let parameter_types = parameters
+          .iter()
+          .map(|_| new_env.new_hole())
+          .collect::<Vec<_>>();
+        // Recursive calls are checked against the parameters as they go.
+        let rec_hole = rec.then(|| {
+          let body_hole = new_env.new_hole();
+          parameter_types
+            .iter()
+            .rfold(body_hole, |acc, param| arr!(param.clone() => acc).into())
+        });
+        if let Some(rec_hole) = &rec_hole {
+          new_env
+            .let_decls
+            .insert(function_name.clone(), Scheme::new(vec![], rec_hole.clone()));
           new_env
-            .variables
-            .insert(param.clone(), Scheme::new(vec![], hole.clone()));
-          parameter_types.push(hole);
+            .link_names
+            .insert(function_name.clone(), link_name.clone());
+          new_env
+            .defined_at
+            .insert(function_name.clone(), src.clone());
+        }
+        let mut binders = Vec::new();
+        for (param, hole) in parameters.iter().zip(&parameter_types) {
+          new_env.record_type(&param.src, hole);
+          binders.push((
+            *param.data.clone(),
+            param.src.clone(),
+            Scheme::new(vec![], hole.clone()),
+          ));
         }
+        new_env.bind(binders, &body.src);
 
+        let body_src = body.src();
         let (elab_body, body_type) = body.infer(new_env.clone());
-        new_env.leave_level();
 
-        let function_type = parameter_types
-          .into_iter()
-          .rfold(body_type, |acc, param| arr!(param => acc).into());
+        let function_type: Type = parameter_types
+          .iter()
+          .rfold(body_type.clone(), |acc, param| {
+            arr!(param.clone() => acc).into()
+          });
+
+        if let Some(rec_hole) = rec_hole {
+          unify(
+            &new_env,
+            rec_hole,
+            function_type.clone(),
+            body_src,
+            Reason::None,
+          );
+        }
+        new_env.leave_level();
 
+        // Only now, as the body of a function that isn't recursive refers
+        // to the definition it shadows.
+        env
+          .link_names
+          .insert(function_name.clone(), link_name.clone());
+        env.defined_at.insert(function_name.clone(), src.clone());
+        env.record_type(&src, &function_type);
+        new_env.default_numbers();
         env.let_decls.insert(
           function_name.clone(),
           new_env.generalize(function_type.clone()),
         );
 
-        let elab_body =
-          parameters
-            .into_iter()
-            .rfold(elab_body, |acc, param| elab::Expression::Fun {
-              variable: param,
-              body: Box::new(acc),
-            });
+        let (elab_body, _) = parameters.into_iter().zip(parameter_types).rfold(
+          (elab_body, body_type),
+          |(body, body_type), (parameter, t)| {
+            let variable = elab::PatternType::Variable {
+              name: *parameter.data,
+            };
+            let fun = elab::ExpressionType::Fun {
+              parameter: elab::Pattern::new(variable, t.clone(), parameter.src),
+              body: Box::new(body),
+            };
+            let t: Type = arr!(t => body_type).into();
+            (elab::Expression::new(fun, t.clone(), src.clone()), t)
+          },
+        );
 
-        let elab = elab::TopLevel::Function(elab::Function {
-          name: function_name,
+        let mut function = elab::Function {
+          public,
+          name: link_name,
+          source: function_name,
           rec,
           body: elab_body,
-        });
+          src,
+        };
+        env.zonk_function(&mut function);
+        let elab = elab::TopLevel::Function(function);
 
         ((env, elab), function_type)
       }
       TopLevel::Enum(Enum {
-        name: enum_name,
+        public,
+        name,
         parameters,
         variants,
+        src,
       }) => {
+        let enum_name = env.link_name(&name);
+        env.link_names.insert(name, enum_name.clone());
+
         env.enum_decls.insert(enum_name.clone(), variants.len());
 
         let scheme = parameters.clone();
@@ -83,18 +146,29 @@ impl Infer for TopLevel {
           env.type_variables.insert(name.clone(), generalized);
         }
 
-        for variant in variants.iter() {
+        let enum_type = Type::new(TypeKind::Enum {
+          name: enum_name.clone(),
+        });
+        let mut elab_variants = Vec::new();
+        for variant in variants {
+          env.record_type(&variant.src, &enum_type);
+          env
+            .defined_at
+            .insert(format!(".{}", variant.data), variant.src);
           env
             .variant_to_enum
-            .insert(variant.clone(), enum_name.clone());
+            .insert(*variant.data.clone(), enum_name.clone());
+          elab_variants.push(*variant.data);
         }
 
         let elab = elab::TopLevel::Enum(elab::Enum {
-          name: enum_name.clone(),
-          variants,
+          public,
+          name: enum_name,
+          variants: elab_variants,
+          src,
         });
 
-        ((env, elab), Type::new(TypeKind::Enum { name: enum_name }))
+        ((env, elab), enum_type)
       }
     }
   }
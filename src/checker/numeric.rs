@@ -0,0 +1,303 @@
+use crate::{
+  ast::Src,
+  report::{Diagnostic, Severity},
+};
+
+use super::{Env, HoleKind, Type, TypeKind};
+
+/// A type that must end up numeric, and what required it.
+pub struct Numeric {
+  t: Type,
+  origin: Origin,
+  src: Src,
+}
+
+#[derive(Clone, Debug)]
+pub enum Origin {
+  /// An integer literal, which may be of any numeric type it fits in.
+  Literal { value: i64 },
+  /// An operand of an arithmetic operator.
+  Operand { op: String },
+  /// An operand of a comparison, which may also be a char.
+  Comparison { op: String },
+  /// An operand of a bitwise or shift operator, which must be an integer.
+  BitOperand { op: String },
+  /// The operand of a prefix `-`.
+  Negation,
+}
+
+pub enum NumericError {
+  NotNumeric {
+    t: String,
+    origin: Origin,
+    src: Src,
+  },
+  /// A `u24` negated.
+  Unsigned {
+    src: Src,
+  },
+  OutOfRange {
+    t: String,
+    range: Option<(i64, i64)>,
+    src: Src,
+  },
+}
+
+/// Whether a known type `t` is one `origin` takes. A `u24` can't be negated
+/// either, which is reported on its own.
+fn fits(t: &TypeKind, origin: &Origin) -> bool {
+  match (t, origin) {
+    (TypeKind::Error, _) => true,
+    (TypeKind::U24 | TypeKind::I24, Origin::BitOperand { .. }) => true,
+    (_, Origin::BitOperand { .. }) => false,
+    (TypeKind::Char, Origin::Comparison { .. }) => true,
+    (t, _) => t.is_numeric(),
+  }
+}
+
+/// The smallest and largest integers of a numeric type.
+fn range(t: &TypeKind) -> Option<(i64, i64)> {
+  match t {
+    TypeKind::U24 => Some((0, (1 << 24) - 1)),
+    TypeKind::I24 => Some((-(1 << 23), (1 << 23) - 1)),
+    _ => None,
+  }
+}
+
+impl Env {
+  /// Requires `t` to be `u24`, `i24` or `f24`, or only one of the integer
+  /// types for bitwise operands. Comparisons also take chars.
+  ///
+  /// Types that are still unknown when a top level definition is
+  /// generalized default to `i24`, so numbers never become polymorphic.
+  pub fn constrain_numeric(&mut self, t: Type, origin: Origin, src: Src) {
+    let numeric = Numeric { t, origin, src };
+    if let Some(numeric) = self.solve_numeric(numeric) {
+      self.numeric.borrow_mut().push(numeric);
+    }
+  }
+
+  /// Lowers the holes of the pending numeric constraints to the current
+  /// level, so that a local `let` doesn't generalize them and the uses of
+  /// its binders can still decide them.
+  pub fn keep_numbers(&mut self) {
+    let level = self.level;
+    for numeric in self.numeric.borrow().iter() {
+      if let TypeKind::Hole { hole } = &*numeric.t.clone().resolve() {
+        if let HoleKind::Unbound { level: l, .. } = &mut *hole.get_mut() {
+          *l = (*l).min(level);
+        }
+      }
+    }
+  }
+
+  /// Checks the pending numeric constraints, defaulting the types about to
+  /// be generalized to `i24`.
+  pub fn default_numbers(&mut self) {
+    let level = self.level;
+    let pending = std::mem::take(&mut *self.numeric.borrow_mut());
+    for numeric in pending {
+      if let TypeKind::Hole { hole } = &*numeric.t.clone().resolve() {
+        if matches!(hole.get(), HoleKind::Unbound { level: l, .. } if l > level) {
+          hole.fill(TypeKind::i24());
+        }
+      }
+      if let Some(numeric) = self.solve_numeric(numeric) {
+        self.numeric.borrow_mut().push(numeric);
+      }
+    }
+  }
+
+  /// Takes out the pending constraints of integer literals whose types are
+  /// now known not to be numeric, returning whether there were any, so a
+  /// unification that just decided them can report them in its context.
+  pub fn take_non_numeric_literals(&self) -> bool {
+    let mut pending = self.numeric.borrow_mut();
+    let before = pending.len();
+    pending.retain(|numeric| {
+      let resolved = numeric.t.clone().resolve();
+      match (&*resolved, &numeric.origin) {
+        (TypeKind::Hole { .. } | TypeKind::Error, _) => true,
+        (t, Origin::Literal { .. }) => t.is_numeric(),
+        _ => true,
+      }
+    });
+    pending.len() < before
+  }
+
+  /// Checks an operand of type `t` against what `origin` requires, as soon
+  /// as `t` is known, reporting it at `src`. Returns whether it fits.
+  pub fn check_operand(&mut self, t: &Type, origin: &Origin, src: &Src) -> bool {
+    let resolved = t.clone().resolve();
+    if matches!(&*resolved, TypeKind::Hole { .. }) || fits(&resolved, origin) {
+      return true;
+    }
+    self.reporter.report(NumericError::NotNumeric {
+      t: resolved.to_string(),
+      origin: origin.clone(),
+      src: src.clone(),
+    });
+    false
+  }
+
+  /// Whether `t` is a hole that a pending constraint requires to be
+  /// numeric, so it can't be anything else, such as a function.
+  pub fn is_pending_numeric(&self, t: &Type) -> bool {
+    !self.pending(t).is_empty()
+  }
+
+  /// How to describe `t` if it is a hole that pending constraints require
+  /// to be numeric and `other` is a known type they don't take, so that
+  /// unifying them, for something at `src`, is reported right there.
+  pub fn numeric_conflict(&self, t: &Type, other: &Type, src: &Src) -> Option<&'static str> {
+    let other = other.clone().resolve();
+    if matches!(&*other, TypeKind::Hole { .. }) {
+      return None;
+    }
+    let origins = self.pending(t);
+    if origins.iter().all(|(origin, _)| fits(&other, origin)) {
+      return None;
+    }
+    let literal = origins
+      .iter()
+      .any(|(origin, at)| matches!(origin, Origin::Literal { .. }) && at == src);
+    let comparisons = origins
+      .iter()
+      .all(|(origin, _)| matches!(origin, Origin::Comparison { .. }));
+    Some(match (literal, comparisons) {
+      (true, _) => "an integer literal",
+      (false, true) => "a number or char",
+      (false, false) => "a number",
+    })
+  }
+
+  /// What the pending constraints on the hole `t` require it for.
+  fn pending(&self, t: &Type) -> Vec<(Origin, Src)> {
+    let TypeKind::Hole { hole } = &*t.clone().resolve() else {
+      return Vec::new();
+    };
+    self
+      .numeric
+      .borrow()
+      .iter()
+      .filter(|numeric| {
+        matches!(&*numeric.t.clone().resolve(), TypeKind::Hole { hole: other } if other == hole)
+      })
+      .map(|numeric| (numeric.origin.clone(), numeric.src.clone()))
+      .collect()
+  }
+
+  pub fn float_out_of_range(&mut self, src: Src) {
+    self.reporter.report(NumericError::OutOfRange {
+      t: TypeKind::F24.to_string(),
+      range: None,
+      src,
+    });
+  }
+
+  /// Checks a constraint, returning it back if its type is still unknown.
+  fn solve_numeric(&mut self, numeric: Numeric) -> Option<Numeric> {
+    let resolved = numeric.t.clone().resolve();
+    match (&*resolved, &numeric.origin) {
+      (TypeKind::Hole { .. }, _) => return Some(numeric),
+      (TypeKind::Error, _) => {}
+      (t, Origin::Literal { value }) if t.is_numeric() => {
+        if let Some((min, max)) = range(t) {
+          if *value < min || *value > max {
+            self.reporter.report(NumericError::OutOfRange {
+              t: t.to_string(),
+              range: Some((min, max)),
+              src: numeric.src,
+            });
+          }
+        }
+      }
+      (TypeKind::U24, Origin::Negation) => self
+        .reporter
+        .report(NumericError::Unsigned { src: numeric.src }),
+      (t, origin) if fits(t, origin) => {}
+      _ => self.reporter.report(NumericError::NotNumeric {
+        t: resolved.to_string(),
+        origin: numeric.origin,
+        src: numeric.src,
+      }),
+    }
+    None
+  }
+}
+
+impl Diagnostic for NumericError {
+  fn message(&self) -> String {
+    match self {
+      NumericError::NotNumeric {
+        origin: Origin::Literal { .. },
+        ..
+      } => "Integer literal used as a non numeric value.".to_string(),
+      NumericError::NotNumeric {
+        origin: Origin::Operand { op },
+        ..
+      } => format!("Operands of `{op}` must be numbers."),
+      NumericError::NotNumeric {
+        origin: Origin::Comparison { op },
+        ..
+      } => format!("Operands of `{op}` must be numbers or chars."),
+      NumericError::NotNumeric {
+        origin: Origin::BitOperand { op },
+        ..
+      } => format!("Operands of `{op}` must be integers."),
+      NumericError::NotNumeric {
+        origin: Origin::Negation,
+        ..
+      } => "Only numbers can be negated.".to_string(),
+      NumericError::Unsigned { .. } => "Only signed numbers can be negated.".to_string(),
+      NumericError::OutOfRange { t, .. } => format!("Literal out of range for {t}."),
+    }
+  }
+
+  fn severity(&self) -> Severity {
+    Severity::Error
+  }
+
+  fn code(&self) -> &'static str {
+    match self {
+      NumericError::NotNumeric { .. } => "not-numeric",
+      NumericError::Unsigned { .. } => "unsigned-negation",
+      NumericError::OutOfRange { .. } => "literal-out-of-range",
+    }
+  }
+
+  fn extra(&self) -> Vec<String> {
+    match self {
+      NumericError::NotNumeric {
+        t,
+        origin: Origin::BitOperand { .. },
+        ..
+      } => vec![format!("Expected u24 or i24 but got {t}.")],
+      NumericError::NotNumeric {
+        t,
+        origin: Origin::Comparison { .. },
+        ..
+      } => vec![format!("Expected u24, i24, f24 or char but got {t}.")],
+      NumericError::NotNumeric { t, .. } => {
+        vec![format!("Expected u24, i24 or f24 but got {t}.")]
+      }
+      NumericError::OutOfRange {
+        t,
+        range: Some((min, max)),
+        ..
+      } => vec![format!("{t} ranges from {min} to {max}.")],
+      NumericError::Unsigned { .. } => {
+        vec!["It is a u24, convert it with `u24_to_i24` first.".to_string()]
+      }
+      NumericError::OutOfRange { range: None, .. } => vec![],
+    }
+  }
+
+  fn src(&self) -> Option<Src> {
+    match self {
+      NumericError::NotNumeric { src, .. } => Some(src.clone()),
+      NumericError::Unsigned { src } => Some(src.clone()),
+      NumericError::OutOfRange { src, .. } => Some(src.clone()),
+    }
+  }
+}
@@ -3,14 +3,34 @@ use std::{cell::RefCell, rc::Rc};
 #[derive(Debug)]
 pub enum TypeKind {
   Unit,
-  Variable { name: String },
-  Generalized { id: usize },
-  Hole { hole: Hole },
-  Arrow { t1: Rc<TypeKind>, t2: Rc<TypeKind> },
-  Enum { name: String },
-  Tuple { elements: Vec<Type> },
-  Number,
+  Variable {
+    name: String,
+  },
+  Generalized {
+    id: usize,
+  },
+  Hole {
+    hole: Hole,
+  },
+  Arrow {
+    t1: Rc<TypeKind>,
+    t2: Rc<TypeKind>,
+  },
+  Enum {
+    name: String,
+  },
+  Tuple {
+    elements: Vec<Type>,
+  },
+  List {
+    element: Type,
+  },
+  U24,
+  I24,
+  F24,
   String,
+  /// A unicode code point, a `u24` in Bend.
+  Char,
   Boolean,
   Error,
 }
@@ -71,7 +91,7 @@ impl Hole {
 impl TypeKind {
   pub fn instantiate(self: Type, substitutions: &[Type]) -> Type {
     match &&*self {
-      TypeKind::Unit { .. } => self.clone(),
+      TypeKind::Unit => self.clone(),
       TypeKind::Variable { .. } => self.clone(),
       TypeKind::Generalized { id } => substitutions[*id].clone(),
       TypeKind::Hole { hole } => match hole.get() {
@@ -90,40 +110,74 @@ impl TypeKind {
           .map(|e| e.clone().instantiate(substitutions))
           .collect::<Vec<_>>(),
       }),
-      TypeKind::Number => self.clone(),
+      TypeKind::List { element } => Type::new(TypeKind::List {
+        element: element.clone().instantiate(substitutions),
+      }),
+      TypeKind::U24 => self.clone(),
+      TypeKind::I24 => self.clone(),
+      TypeKind::F24 => self.clone(),
       TypeKind::String => self.clone(),
+      TypeKind::Char => self.clone(),
       TypeKind::Boolean => self.clone(),
       TypeKind::Error => self.clone(),
     }
   }
 
-  pub fn number() -> Type {
-    Type::new(TypeKind::Number)
+  /// Follows bound holes until reaching a type that is not a bound hole.
+  pub fn resolve(self: Type) -> Type {
+    match &*self {
+      TypeKind::Hole { hole } => match hole.get() {
+        HoleKind::Bound { t } => t.resolve(),
+        HoleKind::Unbound { .. } => self,
+      },
+      _ => self,
+    }
+  }
+
+  pub fn u24() -> Type {
+    Type::new(TypeKind::U24)
+  }
+
+  pub fn i24() -> Type {
+    Type::new(TypeKind::I24)
+  }
+
+  pub fn f24() -> Type {
+    Type::new(TypeKind::F24)
+  }
+
+  pub fn is_numeric(&self) -> bool {
+    matches!(self, TypeKind::U24 | TypeKind::I24 | TypeKind::F24)
   }
 
   pub fn string() -> Type {
     Type::new(TypeKind::String)
   }
 
+  pub fn char() -> Type {
+    Type::new(TypeKind::Char)
+  }
+
   pub fn boolean() -> Type {
     Type::new(TypeKind::Boolean)
   }
 
-  pub fn num_num() -> Type {
-    arr!(TypeKind::Number => arr!(TypeKind::Number => TypeKind::Number)).into()
+  pub fn list(element: Type) -> Type {
+    Type::new(TypeKind::List { element })
   }
 
-  pub fn num_logical() -> Type {
-    arr!(TypeKind::Number => arr!(TypeKind::Number => TypeKind::Boolean)).into()
+  /// 'a -> 'a -> 'a
+  pub fn same_same(a: Type) -> Type {
+    arr!(a.clone() => arr!(a.clone() => a)).into()
   }
 
-  pub fn str_str() -> Type {
-    arr!(TypeKind::String => arr!(TypeKind::String => TypeKind::String)).into()
+  /// 'a -> 'a -> bool
+  pub fn same_logical(a: Type) -> Type {
+    arr!(a.clone() => arr!(a => TypeKind::Boolean)).into()
   }
 
-  /// string -> ()
-  pub fn print_string() -> Type {
-    arr!(TypeKind::String => TypeKind::Unit).into()
+  pub fn str_str() -> Type {
+    arr!(TypeKind::String => arr!(TypeKind::String => TypeKind::String)).into()
   }
 }
 
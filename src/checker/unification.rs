@@ -1,75 +1,207 @@
 use crate::{ast::Src, checker::TypeKind, report::Diagnostic};
 
-use super::{Env, Hole, HoleKind, Type};
+use super::{display::TypeNames, Env, Hole, HoleKind, Type};
 
-pub struct UnifyError(Type, Type, Src);
+/// Where the expected type of a unification came from.
+#[derive(Clone, Debug)]
+pub enum Reason {
+  /// No additional context.
+  None,
+  /// The condition of an `if`.
+  Condition,
+  /// The `else` branch of an `if`, checked against the `then` branch.
+  Branches { then: Src },
+  /// A match arm pattern, checked against the scrutinee.
+  Pattern { scrutinee: Src },
+  /// A match arm body, checked against the first arm.
+  Arms { first: Src },
+  /// The nth argument (starting at 1) of an application.
+  Argument {
+    function: Option<String>,
+    index: usize,
+    function_src: Src,
+  },
+  /// An operand of a binary operator.
+  Operand { op: String },
+  /// A value interpolated in a string.
+  Interpolation,
+}
+
+enum Mismatch {
+  Types(Type, Type),
+  Occurs(Hole, Type),
+}
+
+// Types are rendered when the error is found, as holes keep being filled
+// (and generalized) before the diagnostics are displayed.
+pub struct UnifyError {
+  expected: String,
+  actual: String,
+  mismatch: Option<(String, String)>,
+  reason: Reason,
+  src: Src,
+}
 
-pub struct OccursCheck(Src);
+pub struct OccursCheck(String, String, Src);
 
-fn occurs(hole: Hole, t: Type) -> bool {
+/// Whether `hole` appears in `t`. The holes of `t` are lowered to the
+/// `level` of `hole`, so that they aren't generalized while it is in scope.
+fn occurs(hole: Hole, level: usize, t: Type) -> bool {
   match &*t {
     TypeKind::Unit => false,
     TypeKind::Variable { .. } => false,
     TypeKind::Generalized { .. } => false,
-    TypeKind::Hole { hole: this_hole } => this_hole.clone() == hole,
-    TypeKind::Arrow { t1, t2 } => occurs(hole.clone(), t1.clone()) || occurs(hole, t2.clone()),
+    TypeKind::Hole { hole: this_hole } => match &mut *this_hole.get_mut() {
+      HoleKind::Bound { t } => occurs(hole, level, t.clone()),
+      HoleKind::Unbound {
+        level: this_level, ..
+      } => {
+        *this_level = (*this_level).min(level);
+        *this_hole == hole
+      }
+    },
+    TypeKind::Arrow { t1, t2 } => {
+      occurs(hole.clone(), level, t1.clone()) || occurs(hole, level, t2.clone())
+    }
     TypeKind::Enum { .. } => false,
-    TypeKind::Tuple { elements } => elements.iter().any(|e| occurs(hole.clone(), e.clone())),
-    TypeKind::Number => false,
+    TypeKind::Tuple { elements } => elements
+      .iter()
+      .any(|e| occurs(hole.clone(), level, e.clone())),
+    TypeKind::List { element } => occurs(hole, level, element.clone()),
+    TypeKind::U24 => false,
+    TypeKind::I24 => false,
+    TypeKind::F24 => false,
     TypeKind::String => false,
+    TypeKind::Char => false,
     TypeKind::Boolean => false,
     TypeKind::Error => false,
   }
 }
 
-pub fn unify(env: &Env, t1: Type, t2: Type, src: Src) -> bool {
+/// Unifies the `expected` type with the `actual` one, reporting a diagnostic
+/// pointing at `src` on failure.
+pub fn unify(env: &Env, expected: Type, actual: Type, src: Src, reason: Reason) -> bool {
+  // A number meeting a type it can't be is reported before it is decided,
+  // so that the error is where the two meet and not at every use of it.
+  if !matches!(reason, Reason::None) {
+    let conflict = match env.numeric_conflict(&expected, &actual, &src) {
+      Some(number) => Some((number.to_string(), actual.to_string())),
+      None => env
+        .numeric_conflict(&actual, &expected, &src)
+        .map(|number| (expected.to_string(), number.to_string())),
+    };
+    if let Some((expected, actual)) = conflict {
+      env.reporter.report(UnifyError {
+        expected,
+        actual,
+        mismatch: None,
+        reason,
+        src,
+      });
+      return false;
+    }
+  }
+  match unify_types(expected.clone(), actual.clone()) {
+    // Integer literals are holes until the context decides their type, so
+    // one the context makes non numeric is reported with the context.
+    Ok(()) if !matches!(reason, Reason::None) && env.take_non_numeric_literals() => {
+      env.reporter.report(UnifyError {
+        expected: expected.to_string(),
+        actual: "an integer literal".to_string(),
+        mismatch: None,
+        reason,
+        src,
+      });
+      false
+    }
+    Ok(()) => true,
+    Err(Mismatch::Types(a, b)) => {
+      let names = TypeNames::default();
+      let (expected_shown, actual_shown) = (names.show(&expected), names.show(&actual));
+      let mismatch = if Type::ptr_eq(&a, &expected.clone().resolve())
+        && Type::ptr_eq(&b, &actual.clone().resolve())
+      {
+        None
+      } else {
+        Some((names.show(&a), names.show(&b)))
+      };
+      env.reporter.report(UnifyError {
+        expected: expected_shown,
+        actual: actual_shown,
+        mismatch,
+        reason,
+        src,
+      });
+      false
+    }
+    Err(Mismatch::Occurs(hole, t)) => {
+      let names = TypeNames::default();
+      let hole = names.show(&TypeKind::Hole { hole });
+      env.reporter.report(OccursCheck(hole, names.show(&t), src));
+      false
+    }
+  }
+}
+
+fn unify_types(t1: Type, t2: Type) -> Result<(), Mismatch> {
   use TypeKind::*;
   match (&*t1, &*t2) {
-    (Variable { name: x }, Variable { name: y }) => x == y,
+    (Error, _) | (_, Error) => Ok(()),
 
-    (Generalized { id: x }, Generalized { id: y }) => x == y,
+    (Variable { name: x }, Variable { name: y }) if x == y => Ok(()),
 
-    (Hole { hole }, _) => unify_hole(env, hole.clone(), t2.clone(), false, src),
-    (_, Hole { hole }) => unify_hole(env, hole.clone(), t1.clone(), true, src),
+    (Generalized { id: x }, Generalized { id: y }) if x == y => Ok(()),
+
+    (Hole { hole }, _) => unify_hole(hole.clone(), t2.clone(), false),
+    (_, Hole { hole }) => unify_hole(hole.clone(), t1.clone(), true),
 
     (Arrow { t1: a, t2: b }, Arrow { t1: c, t2: d }) => {
-      unify(env, a.clone(), c.clone(), src.clone()) && unify(env, b.clone(), d.clone(), src)
+      unify_types(a.clone(), c.clone())?;
+      unify_types(b.clone(), d.clone())
     }
 
-    (Enum { name: x }, Enum { name: y }) => x == y,
+    (Enum { name: x }, Enum { name: y }) if x == y => Ok(()),
+
+    (List { element: x }, List { element: y }) => unify_types(x.clone(), y.clone()),
 
-    (Number, Number) => true,
-    (String, String) => true,
-    (Boolean, Boolean) => true,
+    (Unit, Unit) => Ok(()),
+    (U24, U24) => Ok(()),
+    (I24, I24) => Ok(()),
+    (F24, F24) => Ok(()),
+    (String, String) => Ok(()),
+    (Char, Char) => Ok(()),
+    (Boolean, Boolean) => Ok(()),
 
     (Tuple { elements: x }, Tuple { elements: y }) if x.len() == y.len() => x
       .iter()
       .zip(y.iter())
-      .all(|(a, b)| unify(env, a.clone(), b.clone(), src.clone())),
+      .try_for_each(|(a, b)| unify_types(a.clone(), b.clone())),
 
-    (_, _) => {
-      env.reporter.report(UnifyError(t1, t2, src));
-      false
-    }
+    (_, _) => Err(Mismatch::Types(t1, t2)),
   }
 }
 
-fn unify_hole(env: &Env, hole: Hole, t: Type, swap: bool, src: Src) -> bool {
+fn unify_hole(hole: Hole, t: Type, swap: bool) -> Result<(), Mismatch> {
   match hole.get() {
     HoleKind::Bound { t: hole_type } => {
       if swap {
-        unify(env, t, hole_type, src)
+        unify_types(t, hole_type)
       } else {
-        unify(env, hole_type, t, src)
+        unify_types(hole_type, t)
       }
     }
-    HoleKind::Unbound { .. } => {
-      if occurs(hole.clone(), t.clone()) {
-        env.reporter.report(OccursCheck(src));
-        false
+    HoleKind::Unbound { level, .. } => {
+      let t = t.resolve();
+      if let TypeKind::Hole { hole: other } = &*t {
+        if *other == hole {
+          return Ok(());
+        }
+      }
+      if occurs(hole.clone(), level, t.clone()) {
+        Err(Mismatch::Occurs(hole, t))
       } else {
         hole.fill(t);
-        true
+        Ok(())
       }
     }
   }
@@ -77,36 +209,97 @@ fn unify_hole(env: &Env, hole: Hole, t: Type, swap: bool, src: Src) -> bool {
 
 impl Diagnostic for UnifyError {
   fn message(&self) -> String {
-    format!("Type mismatch.")
+    let expected = &self.expected;
+    match &self.reason {
+      Reason::None => "Type mismatch.".to_string(),
+      Reason::Condition => format!("Condition of `if` must be {expected}."),
+      Reason::Branches { .. } => "Both branches of `if` must have the same type.".to_string(),
+      Reason::Pattern { .. } => "Pattern does not match the type of the scrutinee.".to_string(),
+      Reason::Arms { .. } => "All match arms must have the same type.".to_string(),
+      Reason::Argument {
+        function: Some(function),
+        index,
+        ..
+      } => format!("Argument {index} of `{function}` has the wrong type."),
+      Reason::Argument {
+        function: None,
+        index,
+        ..
+      } => format!("Argument {index} has the wrong type."),
+      Reason::Operand { op } => format!("Operand of `{op}` must be {expected}."),
+      Reason::Interpolation => format!("Interpolated values must be {expected}s."),
+    }
   }
 
   fn severity(&self) -> crate::report::Severity {
     crate::report::Severity::Error
   }
 
+  fn code(&self) -> &'static str {
+    "type-mismatch"
+  }
+
   fn extra(&self) -> Vec<String> {
-    vec![format!("Expected {} but got {}.", self.0, self.1)]
+    let mut extra = vec![format!(
+      "Expected {} but got {}.",
+      self.expected, self.actual
+    )];
+    if let Some((a, b)) = &self.mismatch {
+      extra.push(format!("{a} is not compatible with {b}."));
+    }
+    if let Reason::Interpolation = self.reason {
+      extra.push("Convert it to a string first, as with `number_to_string`.".to_string());
+    }
+    extra
   }
 
   fn src(&self) -> Option<crate::ast::Src> {
-    Some(self.2.clone())
+    Some(self.src.clone())
+  }
+
+  fn labels(&self) -> Vec<(Src, String)> {
+    let expected = &self.expected;
+    match &self.reason {
+      Reason::Branches { then } => vec![(then.clone(), format!("then branch is {expected} here"))],
+      Reason::Pattern { scrutinee } => {
+        vec![(scrutinee.clone(), format!("scrutinee is {expected}"))]
+      }
+      Reason::Arms { first } => vec![(first.clone(), format!("first arm was {expected} here"))],
+      Reason::Argument {
+        function,
+        function_src,
+        ..
+      } => {
+        let function = function.as_deref().unwrap_or("function");
+        vec![(
+          function_src.clone(),
+          format!("`{function}` expects {expected}"),
+        )]
+      }
+      _ => vec![],
+    }
   }
 }
 
 impl Diagnostic for OccursCheck {
   fn message(&self) -> String {
-    format!("Occurs check.")
+    "Cannot construct an infinite type.".to_string()
   }
 
   fn severity(&self) -> crate::report::Severity {
     crate::report::Severity::Error
   }
 
+  fn code(&self) -> &'static str {
+    "infinite-type"
+  }
+
   fn extra(&self) -> Vec<String> {
-    vec![]
+    let OccursCheck(hole, t, _) = self;
+    vec![format!("The type {hole} would have to be equal to {t}.")]
   }
 
   fn src(&self) -> Option<crate::ast::Src> {
-    Some(self.0.clone())
+    Some(self.2.clone())
   }
 }
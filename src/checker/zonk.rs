@@ -0,0 +1,198 @@
+use crate::{
+  ast::Src,
+  elab::{self, ExpressionType, Literal, Operation, PatternType, UnaryOperation},
+  report::{Diagnostic, Reporter, Severity},
+};
+
+use super::{Env, Hole, HoleKind, Type, TypeKind};
+
+/// A type that still has holes once the definition it is in was
+/// generalized, so nothing ever decides them.
+pub struct AmbiguousType {
+  t: String,
+  src: Src,
+}
+
+/// Replaces the holes in the types of a definition with what they were
+/// bound to.
+struct Zonker<'a> {
+  reporter: &'a Reporter,
+  /// Whether unbound holes are reported, which they aren't after errors,
+  /// as those leave holes behind.
+  report: bool,
+  /// The unbound holes reported so far, to report each hole only where it
+  /// first appears.
+  reported: Vec<Hole>,
+}
+
+impl Env {
+  /// Resolves every hole in the types of `function`, once it has been
+  /// generalized. Holes that are still unbound are reported and become `()`.
+  pub fn zonk_function(&self, function: &mut elab::Function) {
+    let mut zonker = Zonker {
+      reporter: &self.reporter,
+      report: !self.reporter.has_errors(),
+      reported: Vec::new(),
+    };
+    zonker.expression(&mut function.body);
+  }
+}
+
+impl Zonker<'_> {
+  fn expression(&mut self, expression: &mut elab::Expression) {
+    expression.t = self.zonk(&expression.t, &expression.src);
+    let src = &expression.src;
+    match &mut *expression.data {
+      ExpressionType::Error { .. }
+      | ExpressionType::Unit
+      | ExpressionType::Hole { .. }
+      | ExpressionType::Variable { .. }
+      | ExpressionType::Variant { .. } => {}
+      ExpressionType::Fun { parameter, body } => {
+        self.pattern(parameter);
+        self.expression(body);
+      }
+      ExpressionType::Application { function, argument } => {
+        self.expression(function);
+        self.expression(argument);
+      }
+      ExpressionType::Literal { literal } => self.literal(literal, src),
+      ExpressionType::Let { bind, value, next } => {
+        self.pattern(bind);
+        self.expression(value);
+        self.expression(next);
+      }
+      ExpressionType::If {
+        condition,
+        then,
+        otherwise,
+      } => {
+        self.expression(condition);
+        self.expression(then);
+        self.expression(otherwise);
+      }
+      ExpressionType::Match { scrutinee, arms } => {
+        self.expression(scrutinee);
+        for arm in arms {
+          self.pattern(&mut arm.left);
+          self.expression(&mut arm.right);
+        }
+      }
+      ExpressionType::BinaryOp { op, lhs, rhs } => {
+        if let Operation::Pow { t } | Operation::Shl { t } | Operation::Shr { t } = op {
+          *t = self.zonk(t, src);
+        }
+        self.expression(lhs);
+        self.expression(rhs);
+      }
+      ExpressionType::UnaryOp { op, operand } => {
+        if let UnaryOperation::Neg { t } = op {
+          *t = self.zonk(t, src);
+        }
+        self.expression(operand);
+      }
+      ExpressionType::Tuple { elements } | ExpressionType::List { elements } => {
+        for element in elements {
+          self.expression(element);
+        }
+      }
+      ExpressionType::Projection { tuple, .. } => self.expression(tuple),
+    }
+  }
+
+  fn pattern(&mut self, pattern: &mut elab::Pattern) {
+    pattern.t = self.zonk(&pattern.t, &pattern.src);
+    match &mut *pattern.data {
+      PatternType::Literal { literal } => self.literal(literal, &pattern.src),
+      PatternType::Cons { head, tail } => {
+        self.pattern(head);
+        self.pattern(tail);
+      }
+      PatternType::Tuple { elements } => elements.iter_mut().for_each(|e| self.pattern(e)),
+      PatternType::Error { .. }
+      | PatternType::Wildcard
+      | PatternType::Variable { .. }
+      | PatternType::Variant { .. }
+      | PatternType::Nil => {}
+    }
+  }
+
+  fn literal(&mut self, literal: &mut Literal, src: &Src) {
+    if let Literal::Integer { t, .. } = literal {
+      *t = self.zonk(t, src);
+    }
+  }
+
+  /// `t` without holes, reporting the unbound ones not reported yet at
+  /// `src`.
+  fn zonk(&mut self, t: &Type, src: &Src) -> Type {
+    let mut unbound = Vec::new();
+    let shown = substitute(t, &mut |hole| {
+      unbound.push(hole.clone());
+      Type::new(TypeKind::Variable {
+        name: "_".to_string(),
+      })
+    });
+    if unbound.is_empty() {
+      return shown;
+    }
+
+    if unbound.iter().any(|hole| !self.reported.contains(hole)) {
+      self.reported.extend(unbound);
+      if self.report {
+        self.reporter.report(AmbiguousType {
+          t: shown.to_string(),
+          src: src.clone(),
+        });
+      }
+    }
+    substitute(t, &mut |_| Type::new(TypeKind::Unit))
+  }
+}
+
+/// `t` with its bound holes replaced by their types, and its unbound ones
+/// by what `unbound` gives for them.
+fn substitute(t: &Type, unbound: &mut dyn FnMut(&Hole) -> Type) -> Type {
+  match &**t {
+    TypeKind::Hole { hole } => match hole.get() {
+      HoleKind::Bound { t } => substitute(&t, unbound),
+      HoleKind::Unbound { .. } => unbound(hole),
+    },
+    TypeKind::Arrow { t1, t2 } => Type::new(TypeKind::Arrow {
+      t1: substitute(t1, unbound),
+      t2: substitute(t2, unbound),
+    }),
+    TypeKind::Tuple { elements } => Type::new(TypeKind::Tuple {
+      elements: elements.iter().map(|e| substitute(e, unbound)).collect(),
+    }),
+    TypeKind::List { element } => Type::new(TypeKind::List {
+      element: substitute(element, unbound),
+    }),
+    _ => t.clone(),
+  }
+}
+
+impl Diagnostic for AmbiguousType {
+  fn message(&self) -> String {
+    "The type of this is never fully known.".to_string()
+  }
+
+  fn severity(&self) -> Severity {
+    Severity::Warning
+  }
+
+  fn code(&self) -> &'static str {
+    "ambiguous-type"
+  }
+
+  fn extra(&self) -> Vec<String> {
+    vec![
+      format!("It has type {}, where `_` could be any type.", self.t),
+      "It is compiled as if `_` were ().".to_string(),
+    ]
+  }
+
+  fn src(&self) -> Option<Src> {
+    Some(self.src.clone())
+  }
+}
@@ -1,26 +1,65 @@
 pub mod display;
+pub mod dump;
+pub mod eval;
 pub mod to_bend;
 
+use std::collections::{HashMap, HashSet};
+
+use crate::{ast::Src, checker::types::Type};
+
+/// An elaborated node, with its type once checking is done and the source
+/// it comes from.
+#[derive(Clone, Debug)]
+pub struct Typed<T> {
+  pub data: Box<T>,
+  pub t: Type,
+  pub src: Src,
+}
+
+impl<T> Typed<T> {
+  pub fn new(data: T, t: Type, src: Src) -> Self {
+    Self {
+      data: Box::new(data),
+      t,
+      src,
+    }
+  }
+}
+
 #[derive(Clone, Debug)]
 pub enum Literal {
-  Number { value: i32 },
-  String { value: String },
-  Boolean { value: bool },
+  /// An integer of the numeric type `t`.
+  Integer {
+    value: i64,
+    t: Type,
+  },
+  Float {
+    value: f32,
+  },
+  String {
+    value: String,
+  },
+  Char {
+    value: char,
+  },
+  Boolean {
+    value: bool,
+  },
 }
 
 #[derive(Clone, Debug)]
-pub enum Expression {
+pub enum ExpressionType {
   /// For type error.
   Error { message: String },
   /// ()
   Unit,
   /// ?name
   Hole { name: String },
-  /// a..z | _
-  Variable { name: String },
-  /// fun var -> body
+  /// a..z | _, by its name in the linked program and as written.
+  Variable { name: String, source: String },
+  /// fun pattern -> body
   Fun {
-    variable: String,
+    parameter: Pattern,
     body: Box<Expression>,
   },
   /// f x
@@ -28,11 +67,11 @@ pub enum Expression {
     function: Box<Expression>,
     argument: Box<Expression>,
   },
-  /// num | str | bool
+  /// num | str | char | bool
   Literal { literal: Literal },
-  /// let bind = value in next
+  /// let pattern = value in next
   Let {
-    bind: String,
+    bind: Pattern,
     value: Box<Expression>,
     next: Box<Expression>,
   },
@@ -56,16 +95,78 @@ pub enum Expression {
     lhs: Box<Expression>,
     rhs: Box<Expression>,
   },
+  /// op operand
+  UnaryOp {
+    op: UnaryOperation,
+    operand: Box<Expression>,
+  },
   /// .variant
   Variant { variant: String },
   /// (...,)
   Tuple { elements: Vec<Expression> },
+  /// tuple.index, of a tuple with `size` elements
+  Projection {
+    tuple: Box<Expression>,
+    index: usize,
+    size: usize,
+  },
+  /// [...,]
+  List { elements: Vec<Expression> },
 }
 
-impl Expression {
+pub type Expression = Typed<ExpressionType>;
+
+impl ExpressionType {
   pub fn error(message: String) -> Self {
     Self::Error { message }
   }
+
+  /// Calls `f` with the name of every variable the expression refers to.
+  pub fn variables(&self, f: &mut impl FnMut(&str)) {
+    match self {
+      ExpressionType::Variable { name, .. } => f(name),
+      ExpressionType::Error { .. }
+      | ExpressionType::Unit
+      | ExpressionType::Hole { .. }
+      | ExpressionType::Literal { .. }
+      | ExpressionType::Variant { .. } => {}
+      ExpressionType::Fun { body, .. } => body.data.variables(f),
+      ExpressionType::Application { function, argument } => {
+        function.data.variables(f);
+        argument.data.variables(f);
+      }
+      ExpressionType::Let { value, next, .. } => {
+        value.data.variables(f);
+        next.data.variables(f);
+      }
+      ExpressionType::If {
+        condition,
+        then,
+        otherwise,
+      } => {
+        condition.data.variables(f);
+        then.data.variables(f);
+        otherwise.data.variables(f);
+      }
+      ExpressionType::Match { scrutinee, arms } => {
+        scrutinee.data.variables(f);
+        for arm in arms {
+          arm.right.data.variables(f);
+        }
+      }
+      ExpressionType::BinaryOp { lhs, rhs, .. } => {
+        lhs.data.variables(f);
+        rhs.data.variables(f);
+      }
+      ExpressionType::UnaryOp { operand, .. } => operand.data.variables(f),
+      ExpressionType::Projection { tuple, .. } => tuple.data.variables(f),
+      ExpressionType::Tuple { elements } | ExpressionType::List { elements } => {
+        for element in elements {
+          element.data.variables(f);
+        }
+      }
+    }
+  }
 }
 
 #[derive(Clone, Debug)]
@@ -74,6 +175,21 @@ pub enum Operation {
   Sub,
   Mul,
   Div,
+  Rem,
+  /// Exponentiation, of a number of type `t`.
+  Pow {
+    t: Type,
+  },
+  BitAnd,
+  BitOr,
+  BitXor,
+  /// Shifts of an integer of type `t`.
+  Shl {
+    t: Type,
+  },
+  Shr {
+    t: Type,
+  },
   Gt,
   Gte,
   Lt,
@@ -81,6 +197,54 @@ pub enum Operation {
   Eq,
   Neq,
   Concat,
+  ConcatList,
+  Cons,
+  And,
+  Or,
+}
+
+#[derive(Clone, Debug)]
+pub enum UnaryOperation {
+  /// Negation of a number of type `t`.
+  Neg {
+    t: Type,
+  },
+  Not,
+}
+
+impl Operation {
+  /// Whether the operands can be of any numeric type.
+  pub fn is_numeric(&self) -> bool {
+    !matches!(
+      self,
+      Operation::Concat | Operation::ConcatList | Operation::Cons | Operation::And | Operation::Or
+    )
+  }
+
+  /// Whether the operands can be chars as well as numbers.
+  pub fn is_comparison(&self) -> bool {
+    matches!(
+      self,
+      Operation::Gt
+        | Operation::Gte
+        | Operation::Lt
+        | Operation::Lte
+        | Operation::Eq
+        | Operation::Neq
+    )
+  }
+
+  /// Whether the operands must be integers.
+  pub fn is_bitwise(&self) -> bool {
+    matches!(
+      self,
+      Operation::BitAnd
+        | Operation::BitOr
+        | Operation::BitXor
+        | Operation::Shl { .. }
+        | Operation::Shr { .. }
+    )
+  }
 }
 
 #[derive(Clone, Debug)]
@@ -90,16 +254,33 @@ pub struct Arm {
 }
 
 #[derive(Clone, Debug)]
-pub enum Pattern {
-  Error { message: String },
+pub enum PatternType {
+  Error {
+    message: String,
+  },
   Wildcard,
-  Variable { name: String },
-  Variant { variant: String },
-  Literal { literal: Literal },
-  Tuple { binds: Vec<String> },
+  Variable {
+    name: String,
+  },
+  Variant {
+    variant: String,
+  },
+  Literal {
+    literal: Literal,
+  },
+  Tuple {
+    elements: Vec<Pattern>,
+  },
+  Nil,
+  Cons {
+    head: Box<Pattern>,
+    tail: Box<Pattern>,
+  },
 }
 
-impl Pattern {
+pub type Pattern = Typed<PatternType>;
+
+impl PatternType {
   pub fn error(message: String) -> Self {
     Self::Error { message }
   }
@@ -107,15 +288,22 @@ impl Pattern {
 
 #[derive(Clone, Debug)]
 pub struct Function {
+  pub public: bool,
+  /// The name in the linked program.
   pub name: String,
+  /// The name as written.
+  pub source: String,
   pub rec: bool,
   pub body: Expression,
+  pub src: Src,
 }
 
 #[derive(Clone, Debug)]
 pub struct Enum {
+  pub public: bool,
   pub name: String,
   pub variants: Vec<String>,
+  pub src: Src,
 }
 
 #[derive(Clone, Debug)]
@@ -134,4 +322,39 @@ impl Program {
   pub fn empty() -> Self {
     Self::default()
   }
+
+  /// The functions `entrypoint` refers to, directly or through others,
+  /// itself included.
+  pub fn reachable(&self, entrypoint: &str) -> Program {
+    let functions: HashMap<&str, &Function> = self
+      .declarations
+      .iter()
+      .filter_map(|decl| match decl {
+        TopLevel::Function(function) => Some((function.name.as_str(), function)),
+        TopLevel::Enum(_) => None,
+      })
+      .collect();
+    let mut reached = HashSet::new();
+    let mut pending = vec![entrypoint];
+    while let Some(name) = pending.pop() {
+      if let Some(function) = functions.get(name) {
+        if reached.insert(name) {
+          function.body.data.variables(&mut |name| {
+            if let Some((name, _)) = functions.get_key_value(name) {
+              pending.push(name);
+            }
+          });
+        }
+      }
+    }
+    Program {
+      file_name: self.file_name.clone(),
+      declarations: self
+        .declarations
+        .iter()
+        .filter(|decl| matches!(decl, TopLevel::Function(function) if reached.contains(function.name.as_str())))
+        .cloned()
+        .collect(),
+    }
+  }
 }
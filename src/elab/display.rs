@@ -1,17 +1,33 @@
 use core::fmt;
 
-use super::{Arm, Enum, Expression, Function, Literal, Operation, Pattern, Program, TopLevel};
+use crate::parse::string::{escape, escape_char};
+
+use super::{
+  Arm, Enum, ExpressionType, Function, Literal, Operation, PatternType, Program, TopLevel, Typed,
+  UnaryOperation,
+};
 
 impl fmt::Display for Literal {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     match self {
-      Literal::Number { value } => write!(f, "{value}"),
-      Literal::String { value } => write!(f, "{value:?}"),
+      Literal::Integer { value, .. } => write!(f, "{value}"),
+      Literal::Float { value } => write!(f, "{value:?}"),
+      Literal::String { value } => write!(f, "{}", escape(value)),
+      Literal::Char { value } => write!(f, "{}", escape_char(*value)),
       Literal::Boolean { value } => write!(f, "{value}"),
     }
   }
 }
 
+impl fmt::Display for UnaryOperation {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      UnaryOperation::Neg { .. } => write!(f, "-"),
+      UnaryOperation::Not => write!(f, "not "),
+    }
+  }
+}
+
 impl fmt::Display for Operation {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     match self {
@@ -19,55 +35,77 @@ impl fmt::Display for Operation {
       Operation::Sub => write!(f, "-"),
       Operation::Mul => write!(f, "*"),
       Operation::Div => write!(f, "/"),
+      Operation::Rem => write!(f, "%"),
+      Operation::Pow { .. } => write!(f, "**"),
+      Operation::BitAnd => write!(f, "&"),
+      Operation::BitOr => write!(f, "|"),
+      Operation::BitXor => write!(f, "^"),
+      Operation::Shl { .. } => write!(f, "<<"),
+      Operation::Shr { .. } => write!(f, ">>"),
       Operation::Gt => write!(f, ">"),
       Operation::Gte => write!(f, ">="),
       Operation::Lt => write!(f, "<"),
       Operation::Lte => write!(f, "<="),
       Operation::Eq => write!(f, "="),
-      Operation::Neq => write!(f, "!="),
+      Operation::Neq => write!(f, "<>"),
       Operation::Concat => write!(f, "++"),
+      Operation::ConcatList => write!(f, "++"),
+      Operation::Cons => write!(f, "::"),
+      Operation::And => write!(f, "&&"),
+      Operation::Or => write!(f, "||"),
     }
   }
 }
 
-impl fmt::Display for Pattern {
+impl fmt::Display for PatternType {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     match self {
-      Pattern::Error { message } => write!(f, "<Error: \"{message}\">"),
-      Pattern::Wildcard => write!(f, "_"),
-      Pattern::Variable { name } => write!(f, "{name}"),
-      Pattern::Variant { variant } => write!(f, ".{variant}"),
-      Pattern::Literal { literal } => write!(f, "{literal}"),
-      Pattern::Tuple { binds } => write!(f, "{binds:?}"),
+      PatternType::Error { message } => write!(f, "<Error: \"{message}\">"),
+      PatternType::Wildcard => write!(f, "_"),
+      PatternType::Variable { name } => write!(f, "{name}"),
+      PatternType::Variant { variant } => write!(f, ".{variant}"),
+      PatternType::Literal { literal } => write!(f, "{literal}"),
+      PatternType::Tuple { elements } => {
+        let elements = elements.iter().map(ToString::to_string).collect::<Vec<_>>();
+        write!(f, "({})", elements.join(", "))
+      }
+      PatternType::Nil => write!(f, "[]"),
+      PatternType::Cons { head, tail } => write!(f, "{head} :: {tail}"),
     }
   }
 }
 
+impl<T: fmt::Display> fmt::Display for Typed<T> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.data)
+  }
+}
+
 impl fmt::Display for Arm {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     write!(f, "{} => {}", self.left, self.right)
   }
 }
 
-impl fmt::Display for Expression {
+impl fmt::Display for ExpressionType {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     match self {
-      Expression::Error { message } => write!(f, "<Error: \"{message}\">"),
-      Expression::Unit => write!(f, "()"),
-      Expression::Hole { name } => write!(f, "?{name}"),
-      Expression::Variable { name } => write!(f, "{name}"),
-      Expression::Fun { variable, body } => write!(f, "fun {variable} -> {body}"),
-      Expression::Application { function, argument } => write!(f, "({function} {argument})"),
-      Expression::Literal { literal } => write!(f, "{literal}"),
-      Expression::Let { bind, value, next } => write!(f, "let {bind} = {value} in {next}"),
-      Expression::If {
+      ExpressionType::Error { message } => write!(f, "<Error: \"{message}\">"),
+      ExpressionType::Unit => write!(f, "()"),
+      ExpressionType::Hole { name } => write!(f, "?{name}"),
+      ExpressionType::Variable { name, .. } => write!(f, "{name}"),
+      ExpressionType::Fun { parameter, body } => write!(f, "fun {parameter} -> {body}"),
+      ExpressionType::Application { function, argument } => write!(f, "({function} {argument})"),
+      ExpressionType::Literal { literal } => write!(f, "{literal}"),
+      ExpressionType::Let { bind, value, next } => write!(f, "let {bind} = {value} in {next}"),
+      ExpressionType::If {
         condition,
         then,
         otherwise,
       } => {
         write!(f, "if {condition} then {then} else {otherwise}")
       }
-      Expression::Match { scrutinee, arms } => {
+      ExpressionType::Match { scrutinee, arms } => {
         write!(f, "match {scrutinee} with ")?;
         for arm in arms {
           write!(f, "{arm}, ")?;
@@ -75,15 +113,39 @@ impl fmt::Display for Expression {
         write!(f, "end")?;
         Ok(())
       }
-      Expression::BinaryOp { op, lhs, rhs } => write!(f, "({lhs} {op} {rhs})"),
-      Expression::Variant { variant } => write!(f, ".{variant}"),
-      Expression::Tuple { elements } => write!(f, "({elements:?})"),
+      ExpressionType::BinaryOp { op, lhs, rhs } => write!(f, "({lhs} {op} {rhs})"),
+      ExpressionType::UnaryOp { op, operand } => write!(f, "({op}{operand})"),
+      ExpressionType::Variant { variant } => write!(f, ".{variant}"),
+      ExpressionType::Tuple { elements } => {
+        write!(f, "(")?;
+        for (i, element) in elements.iter().enumerate() {
+          if i > 0 {
+            write!(f, ", ")?;
+          }
+          write!(f, "{element}")?;
+        }
+        write!(f, ")")
+      }
+      ExpressionType::Projection { tuple, index, .. } => write!(f, "{tuple}.{index}"),
+      ExpressionType::List { elements } => {
+        write!(f, "[")?;
+        for (i, element) in elements.iter().enumerate() {
+          if i > 0 {
+            write!(f, ", ")?;
+          }
+          write!(f, "{element}")?;
+        }
+        write!(f, "]")
+      }
     }
   }
 }
 
 impl fmt::Display for Function {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if self.public {
+      write!(f, "pub ")?;
+    }
     write!(f, "let ")?;
     if self.rec {
       write!(f, "rec ")?;
@@ -96,6 +158,9 @@ impl fmt::Display for Function {
 
 impl fmt::Display for Enum {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if self.public {
+      write!(f, "pub ")?;
+    }
     write!(f, "enum ")?;
     write!(f, "{} ", self.name)?;
     write!(f, " := ")?;
@@ -0,0 +1,141 @@
+use std::fmt::Write;
+
+use crate::{
+  ast::Src,
+  checker::{display::TypeNames, types::Type},
+  format,
+  source::{FileId, SourceMap},
+};
+
+use super::{Expression, ExpressionType, Pattern, PatternType, Program, TopLevel};
+
+/// Writes elaborated nodes one per line, indented under their parent, with
+/// where they are and their type.
+struct Dump<'a> {
+  sources: &'a SourceMap,
+  out: String,
+  depth: usize,
+  /// The names of the type variables of the declaration being written.
+  names: TypeNames,
+}
+
+impl Program {
+  /// The declarations written in `file` with the type of every node, for
+  /// `oktu check --dump-typed`.
+  pub fn dump_typed(&self, file: FileId, sources: &SourceMap) -> String {
+    let mut dump = Dump {
+      sources,
+      out: String::new(),
+      depth: 1,
+      names: TypeNames::default(),
+    };
+    for declaration in &self.declarations {
+      match declaration {
+        TopLevel::Function(function) if function.src.file == file => {
+          let rec = if function.rec { "rec " } else { "" };
+          dump.names = TypeNames::default();
+          let t = dump.names.show(&function.body.t);
+          let name = format::name(&function.source);
+          _ = writeln!(dump.out, "let {rec}{name} : {t}");
+          dump.expression(&function.body);
+          dump.out.push('\n');
+        }
+        TopLevel::Enum(r#enum) if r#enum.src.file == file => {
+          _ = writeln!(dump.out, "{enum}\n");
+        }
+        _ => {}
+      }
+    }
+    dump.out
+  }
+}
+
+impl Dump<'_> {
+  /// Writes a line for the node at `src` of type `t`, with `children`
+  /// indented under it.
+  fn node(&mut self, label: &str, t: &Type, src: &Src, children: impl FnOnce(&mut Self)) {
+    let (line, column) = self.sources.get(src.file).line_col(src.range.start);
+    let position = format!("{line}:{column}");
+    let indent = "  ".repeat(self.depth);
+    let t = self.names.show(t);
+    _ = writeln!(self.out, "{position:>7}{indent}{label} : {t}");
+    self.depth += 1;
+    children(self);
+    self.depth -= 1;
+  }
+
+  fn expression(&mut self, expression: &Expression) {
+    let (t, src) = (&expression.t, &expression.src);
+    match &*expression.data {
+      ExpressionType::Error { message } => {
+        self.node(&format!("<error: {message}>"), t, src, |_| {})
+      }
+      ExpressionType::Unit => self.node("()", t, src, |_| {}),
+      ExpressionType::Hole { name } => self.node(&format!("?{name}"), t, src, |_| {}),
+      ExpressionType::Variable { source, .. } => self.node(source, t, src, |_| {}),
+      ExpressionType::Fun { parameter, body } => self.node("fun", t, src, |dump| {
+        dump.pattern(parameter);
+        dump.expression(body);
+      }),
+      ExpressionType::Application { function, argument } => self.node("apply", t, src, |dump| {
+        dump.expression(function);
+        dump.expression(argument);
+      }),
+      ExpressionType::Literal { literal } => self.node(&literal.to_string(), t, src, |_| {}),
+      ExpressionType::Let { bind, value, next } => self.node("let", t, src, |dump| {
+        dump.pattern(bind);
+        dump.expression(value);
+        dump.expression(next);
+      }),
+      ExpressionType::If {
+        condition,
+        then,
+        otherwise,
+      } => self.node("if", t, src, |dump| {
+        dump.expression(condition);
+        dump.expression(then);
+        dump.expression(otherwise);
+      }),
+      ExpressionType::Match { scrutinee, arms } => self.node("match", t, src, |dump| {
+        dump.expression(scrutinee);
+        for arm in arms {
+          dump.pattern(&arm.left);
+          dump.depth += 1;
+          dump.expression(&arm.right);
+          dump.depth -= 1;
+        }
+      }),
+      ExpressionType::BinaryOp { op, lhs, rhs } => self.node(&op.to_string(), t, src, |dump| {
+        dump.expression(lhs);
+        dump.expression(rhs);
+      }),
+      ExpressionType::UnaryOp { op, operand } => self.node(op.to_string().trim(), t, src, |dump| {
+        dump.expression(operand)
+      }),
+      ExpressionType::Variant { variant } => self.node(&format!(".{variant}"), t, src, |_| {}),
+      ExpressionType::Tuple { elements } => self.node("tuple", t, src, |dump| {
+        elements.iter().for_each(|element| dump.expression(element))
+      }),
+      ExpressionType::Projection { tuple, index, .. } => {
+        self.node(&format!(".{index}"), t, src, |dump| dump.expression(tuple))
+      }
+      ExpressionType::List { elements } => self.node("list", t, src, |dump| {
+        elements.iter().for_each(|element| dump.expression(element))
+      }),
+    }
+  }
+
+  fn pattern(&mut self, pattern: &Pattern) {
+    let (t, src) = (&pattern.t, &pattern.src);
+    match &*pattern.data {
+      PatternType::Cons { head, tail } => self.node("::", t, src, |dump| {
+        dump.pattern(head);
+        dump.pattern(tail);
+      }),
+      PatternType::Tuple { elements } => self.node("tuple", t, src, |dump| {
+        elements.iter().for_each(|element| dump.pattern(element))
+      }),
+      data => self.node(&data.to_string(), t, src, |_| {}),
+    }
+  }
+}
@@ -0,0 +1,701 @@
+use std::{cmp::Ordering, collections::HashMap, fmt, io::Write, rc::Rc};
+
+use super::{
+  Expression, ExpressionType, Literal, Operation, Pattern, PatternType, Program, TopLevel,
+  UnaryOperation,
+};
+use crate::{
+  ast::Src,
+  builtins::{Builtin, BUILTINS},
+  checker::types::TypeKind,
+  parse::string::{escape, escape_char},
+  report::{Diagnostic, Severity},
+};
+
+const U24_MASK: u32 = 0xFF_FFFF;
+
+/// A number of one of Bend's numeric types. `u24` and `i24` wrap around like
+/// in HVM, `f24` has the precision of an `f32`.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum Number {
+  U24(u32),
+  I24(i32),
+  F24(f32),
+}
+
+fn u24(n: u32) -> Number {
+  Number::U24(n & U24_MASK)
+}
+
+fn i24(n: i32) -> Number {
+  Number::I24((n << 8) >> 8)
+}
+
+#[derive(Clone)]
+pub enum Value<'p> {
+  Unit,
+  Number(Number),
+  Char(char),
+  Boolean(bool),
+  String(Rc<str>),
+  Tuple(Rc<[Value<'p>]>),
+  Nil,
+  Cons(Rc<(Value<'p>, Value<'p>)>),
+  Variant(&'p str),
+  Closure(Rc<Closure<'p>>),
+  /// A builtin applied to fewer arguments than it takes.
+  Builtin {
+    builtin: &'static Builtin,
+    arity: usize,
+    arguments: Vec<Value<'p>>,
+  },
+}
+
+pub struct Closure<'p> {
+  parameter: &'p Pattern,
+  body: &'p Expression,
+  locals: Locals<'p>,
+}
+
+/// Local variables, innermost first.
+#[derive(Clone, Default)]
+struct Locals<'p>(Option<Rc<Local<'p>>>);
+
+struct Local<'p> {
+  name: &'p str,
+  value: Value<'p>,
+  next: Locals<'p>,
+}
+
+impl<'p> Locals<'p> {
+  fn with(&self, name: &'p str, value: Value<'p>) -> Self {
+    Self(Some(Rc::new(Local {
+      name,
+      value,
+      next: self.clone(),
+    })))
+  }
+
+  fn get(&self, name: &str) -> Option<&Value<'p>> {
+    let mut locals = self;
+    while let Some(local) = &locals.0 {
+      if local.name == name {
+        return Some(&local.value);
+      }
+      locals = &local.next;
+    }
+    None
+  }
+}
+
+/// A program that went wrong while being evaluated.
+pub enum RuntimeError {
+  Hole(String, Src),
+  DivisionByZero(Src),
+  NoMatch(String, Src),
+  InvalidChar(u32, Src),
+  /// An entrypoint that isn't defined.
+  Undefined(String),
+  Output(String, Src),
+  /// Something type checking should have ruled out.
+  Stuck(String, Src),
+}
+
+/// Evaluates elaborated programs directly, computing what their Bend
+/// translation does wherever HVM gives a value. Evaluation is strict and
+/// left to right, and definitions are evaluated the first time they are
+/// used.
+pub struct Evaluator<'p, W> {
+  definitions: HashMap<&'p str, &'p Expression>,
+  globals: HashMap<&'p str, Value<'p>>,
+  /// Where `print` writes to.
+  output: W,
+}
+
+impl<'p, W: Write> Evaluator<'p, W> {
+  pub fn new(program: &'p Program, output: W) -> Self {
+    let definitions = program
+      .declarations
+      .iter()
+      .filter_map(|declaration| match declaration {
+        TopLevel::Function(function) => Some((function.name.as_str(), &function.body)),
+        TopLevel::Enum(_) => None,
+      })
+      .collect();
+    Self {
+      definitions,
+      globals: HashMap::new(),
+      output,
+    }
+  }
+
+  /// The value of the top level definition or builtin linked as `name`.
+  pub fn global(&mut self, name: &str) -> Result<Value<'p>, RuntimeError> {
+    if let Some(value) = self.globals.get(name) {
+      return Ok(value.clone());
+    }
+    if let Some((&name, &body)) = self.definitions.get_key_value(name) {
+      let value = self.eval(body, &Locals::default())?;
+      self.globals.insert(name, value.clone());
+      return Ok(value);
+    }
+    match BUILTINS.iter().find(|builtin| builtin.bend == name) {
+      Some(builtin) => Ok(Value::Builtin {
+        builtin,
+        arity: arity(builtin),
+        arguments: vec![],
+      }),
+      None => Err(RuntimeError::Undefined(name.to_string())),
+    }
+  }
+
+  fn eval(
+    &mut self,
+    expression: &'p Expression,
+    locals: &Locals<'p>,
+  ) -> Result<Value<'p>, RuntimeError> {
+    let src = &expression.src;
+    match &*expression.data {
+      ExpressionType::Error { message } => Err(RuntimeError::Stuck(message.clone(), src.clone())),
+      ExpressionType::Unit => Ok(Value::Unit),
+      ExpressionType::Hole { name } => Err(RuntimeError::Hole(name.clone(), src.clone())),
+      ExpressionType::Variable { name, .. } => match locals.get(name) {
+        Some(value) => Ok(value.clone()),
+        None => self.global(name),
+      },
+      ExpressionType::Fun { parameter, body } => Ok(Value::Closure(Rc::new(Closure {
+        parameter,
+        body,
+        locals: locals.clone(),
+      }))),
+      ExpressionType::Application { function, argument } => {
+        let function = self.eval(function, locals)?;
+        let argument = self.eval(argument, locals)?;
+        self.apply(function, argument, src)
+      }
+      ExpressionType::Literal { literal } => Ok(literal_value(literal)),
+      ExpressionType::Let { bind, value, next } => {
+        let value = self.eval(value, locals)?;
+        let locals = irrefutable(bind, &value, locals)?;
+        self.eval(next, &locals)
+      }
+      ExpressionType::If {
+        condition,
+        then,
+        otherwise,
+      } => match self.eval(condition, locals)? {
+        Value::Boolean(true) => self.eval(then, locals),
+        Value::Boolean(false) => self.eval(otherwise, locals),
+        value => Err(RuntimeError::Stuck(
+          format!("`{value}` is not a condition"),
+          src.clone(),
+        )),
+      },
+      ExpressionType::Match { scrutinee, arms } => {
+        let value = self.eval(scrutinee, locals)?;
+        for arm in arms {
+          if let Some(locals) = bind(&arm.left, &value, locals)? {
+            return self.eval(&arm.right, &locals);
+          }
+        }
+        Err(RuntimeError::NoMatch(value.to_string(), src.clone()))
+      }
+      ExpressionType::BinaryOp { op, lhs, rhs } => {
+        let lhs = self.eval(lhs, locals)?;
+        // Short-circuits, the right side only runs when it decides the
+        // result.
+        match (op, &lhs) {
+          (Operation::And, Value::Boolean(false)) | (Operation::Or, Value::Boolean(true)) => {
+            return Ok(lhs)
+          }
+          (Operation::And | Operation::Or, Value::Boolean(_)) => return self.eval(rhs, locals),
+          _ => {}
+        }
+        let rhs = self.eval(rhs, locals)?;
+        binary(op, lhs, rhs, src)
+      }
+      ExpressionType::UnaryOp { op, operand } => match (op, self.eval(operand, locals)?) {
+        (UnaryOperation::Neg { .. }, Value::Number(Number::U24(n))) => {
+          Ok(Value::Number(u24(n.wrapping_neg())))
+        }
+        (UnaryOperation::Neg { .. }, Value::Number(Number::I24(n))) => {
+          Ok(Value::Number(i24(n.wrapping_neg())))
+        }
+        (UnaryOperation::Neg { .. }, Value::Number(Number::F24(n))) => {
+          Ok(Value::Number(Number::F24(-n)))
+        }
+        (UnaryOperation::Not, Value::Boolean(b)) => Ok(Value::Boolean(!b)),
+        (_, value) => Err(RuntimeError::Stuck(
+          format!("bad operand `{value}`"),
+          src.clone(),
+        )),
+      },
+      ExpressionType::Variant { variant } => Ok(Value::Variant(variant)),
+      ExpressionType::Tuple { elements } => Ok(Value::Tuple(
+        elements
+          .iter()
+          .map(|element| self.eval(element, locals))
+          .collect::<Result<_, _>>()?,
+      )),
+      ExpressionType::Projection { tuple, index, .. } => match self.eval(tuple, locals)? {
+        Value::Tuple(elements) if *index < elements.len() => Ok(elements[*index].clone()),
+        value => Err(RuntimeError::Stuck(
+          format!("`{value}` has no element {index}"),
+          src.clone(),
+        )),
+      },
+      ExpressionType::List { elements } => {
+        let elements = elements
+          .iter()
+          .map(|element| self.eval(element, locals))
+          .collect::<Result<Vec<_>, _>>()?;
+        Ok(list(elements, Value::Nil))
+      }
+    }
+  }
+
+  /// Applies `function` to `argument` in the application at `src`.
+  fn apply(
+    &mut self,
+    function: Value<'p>,
+    argument: Value<'p>,
+    src: &Src,
+  ) -> Result<Value<'p>, RuntimeError> {
+    match function {
+      Value::Closure(closure) => {
+        let locals = irrefutable(closure.parameter, &argument, &closure.locals)?;
+        self.eval(closure.body, &locals)
+      }
+      Value::Builtin {
+        builtin,
+        arity,
+        mut arguments,
+      } => {
+        arguments.push(argument);
+        match arguments.len() < arity {
+          true => Ok(Value::Builtin {
+            builtin,
+            arity,
+            arguments,
+          }),
+          false => self.call(builtin, &arguments, src),
+        }
+      }
+      value => Err(RuntimeError::Stuck(
+        format!("`{value}` is not a function"),
+        src.clone(),
+      )),
+    }
+  }
+
+  /// Runs a builtin, as implemented in `oktu.builtins.bend`.
+  fn call(
+    &mut self,
+    builtin: &'static Builtin,
+    arguments: &[Value<'p>],
+    src: &Src,
+  ) -> Result<Value<'p>, RuntimeError> {
+    use Number::*;
+    use Value::Number as N;
+
+    let value = match (builtin.name, arguments) {
+      ("print", [Value::String(text)]) => {
+        write!(self.output, "{text}")
+          .map_err(|e| RuntimeError::Output(e.to_string(), src.clone()))?;
+        Value::Unit
+      }
+      ("string_concat", [Value::String(a), Value::String(b)]) => string(format!("{a}{b}")),
+      ("string_length", [Value::String(s)]) => N(i24(s.chars().count() as i32)),
+      ("substring", [Value::String(s), N(I24(start)), N(I24(length))]) => {
+        string(substring(s, *start, *length))
+      }
+      ("char_at", [Value::String(s), N(I24(index))]) => {
+        Value::Char(s.chars().nth((*index).max(0) as usize).unwrap_or('\0'))
+      }
+      ("string_cons", [Value::Char(c), Value::String(s)]) => string(format!("{c}{s}")),
+      ("string_uncons", [Value::String(s)]) => {
+        let mut chars = s.chars();
+        match chars.next() {
+          Some(c) => {
+            let pair = Value::Tuple(Rc::new([Value::Char(c), string(chars.as_str().into())]));
+            list(vec![pair], Value::Nil)
+          }
+          None => Value::Nil,
+        }
+      }
+      ("char_to_u24", [Value::Char(c)]) => N(U24(*c as u32)),
+      ("u24_to_char", [N(U24(n))]) => {
+        Value::Char(char::from_u32(*n).ok_or_else(|| RuntimeError::InvalidChar(*n, src.clone()))?)
+      }
+      ("string_compare", [Value::String(a), Value::String(b)]) => N(I24(match a.cmp(b) {
+        Ordering::Less => -1,
+        Ordering::Equal => 0,
+        Ordering::Greater => 1,
+      })),
+      ("string_equal", [Value::String(a), Value::String(b)]) => Value::Boolean(a == b),
+      ("number_to_string", [N(I24(n))]) => string(n.to_string()),
+      ("string_to_number", [Value::String(s)]) => match string_to_number(s) {
+        Some(n) => list(vec![N(I24(n))], Value::Nil),
+        None => Value::Nil,
+      },
+      ("min", [N(a), N(b)]) => N(if a < b { *a } else { *b }),
+      ("max", [N(a), N(b)]) => N(if a > b { *a } else { *b }),
+      ("u24_to_i24", [N(U24(n))]) => N(i24(*n as i32)),
+      ("u24_to_f24", [N(U24(n))]) => N(F24(*n as f32)),
+      ("i24_to_u24", [N(I24(n))]) => N(u24(*n as u32)),
+      ("i24_to_f24", [N(I24(n))]) => N(F24(*n as f32)),
+      ("f24_to_u24", [N(F24(x))]) => N(U24(f24_to_u24(*x))),
+      ("f24_to_i24", [N(F24(x))]) => {
+        // The truncated magnitude, reinterpreted as an i24.
+        let n = f24_to_u24(x.abs()) as i32;
+        N(i24(if *x < 0.0 { n.wrapping_neg() } else { n }))
+      }
+      ("list_concat", [xs, ys]) => list(elements(xs), ys.clone()),
+      ("list_length", [xs]) => N(i24(elements(xs).len() as i32)),
+      (name, _) => {
+        return Err(RuntimeError::Stuck(
+          format!("bad arguments to `{name}`"),
+          src.clone(),
+        ))
+      }
+    };
+    Ok(value)
+  }
+}
+
+impl Program {
+  /// Evaluates the definition linked as `entrypoint`, `print` writing to
+  /// `output`.
+  pub fn eval(&self, entrypoint: &str, output: impl Write) -> Result<Value<'_>, RuntimeError> {
+    Evaluator::new(self, output).global(entrypoint)
+  }
+}
+
+/// The number of arguments a builtin takes before it runs.
+fn arity(builtin: &Builtin) -> usize {
+  let mut t = (builtin.scheme)().t;
+  let mut arity = 0;
+  while let TypeKind::Arrow { t2, .. } = &*t.clone() {
+    arity += 1;
+    t = t2.clone();
+  }
+  arity
+}
+
+fn string<'p>(s: String) -> Value<'p> {
+  Value::String(s.into())
+}
+
+/// The list of `elements` followed by `tail`.
+fn list<'p>(elements: Vec<Value<'p>>, tail: Value<'p>) -> Value<'p> {
+  elements
+    .into_iter()
+    .rfold(tail, |tail, head| Value::Cons(Rc::new((head, tail))))
+}
+
+fn elements<'p>(mut xs: &Value<'p>) -> Vec<Value<'p>> {
+  let mut elements = Vec::new();
+  while let Value::Cons(cons) = xs {
+    elements.push(cons.0.clone());
+    xs = &cons.1;
+  }
+  elements
+}
+
+fn literal_value<'p>(literal: &Literal) -> Value<'p> {
+  match literal {
+    Literal::Integer { value, t } => Value::Number(match &*t.clone().resolve() {
+      TypeKind::U24 => u24(*value as u32),
+      TypeKind::F24 => Number::F24(*value as f32),
+      _ => i24(*value as i32),
+    }),
+    Literal::Float { value } => Value::Number(Number::F24(*value)),
+    Literal::String { value } => Value::String(value.as_str().into()),
+    Literal::Char { value } => Value::Char(*value),
+    Literal::Boolean { value } => Value::Boolean(*value),
+  }
+}
+
+/// Binds the variables of `pattern` to the parts of `value`, or `None` if
+/// it doesn't match.
+fn bind<'p>(
+  pattern: &'p Pattern,
+  value: &Value<'p>,
+  locals: &Locals<'p>,
+) -> Result<Option<Locals<'p>>, RuntimeError> {
+  let locals = match (&*pattern.data, value) {
+    (PatternType::Error { message }, _) => {
+      return Err(RuntimeError::Stuck(message.clone(), pattern.src.clone()))
+    }
+    (PatternType::Wildcard, _) => locals.clone(),
+    (PatternType::Variable { name }, _) => locals.with(name, value.clone()),
+    (PatternType::Variant { variant }, Value::Variant(v)) if variant == v => locals.clone(),
+    (PatternType::Literal { literal }, _) if equal(&literal_value(literal), value) => {
+      locals.clone()
+    }
+    (PatternType::Tuple { elements }, Value::Tuple(values)) if elements.len() == values.len() => {
+      let mut locals = locals.clone();
+      for (element, value) in elements.iter().zip(values.iter()) {
+        match bind(element, value, &locals)? {
+          Some(bound) => locals = bound,
+          None => return Ok(None),
+        }
+      }
+      locals
+    }
+    (PatternType::Nil, Value::Nil) => locals.clone(),
+    (PatternType::Cons { head, tail }, Value::Cons(cons)) => match bind(head, &cons.0, locals)? {
+      Some(locals) => return bind(tail, &cons.1, &locals),
+      None => return Ok(None),
+    },
+    _ => return Ok(None),
+  };
+  Ok(Some(locals))
+}
+
+/// Binds a parameter or a `let`, whose patterns always match.
+fn irrefutable<'p>(
+  pattern: &'p Pattern,
+  value: &Value<'p>,
+  locals: &Locals<'p>,
+) -> Result<Locals<'p>, RuntimeError> {
+  bind(pattern, value, locals)?.ok_or_else(|| {
+    RuntimeError::Stuck(
+      format!("`{value}` doesn't match a binding"),
+      pattern.src.clone(),
+    )
+  })
+}
+
+/// Whether two values that can be written as literals are the same.
+fn equal(a: &Value, b: &Value) -> bool {
+  match (a, b) {
+    (Value::Unit, Value::Unit) => true,
+    (Value::Number(a), Value::Number(b)) => a == b,
+    (Value::Char(a), Value::Char(b)) => a == b,
+    (Value::Boolean(a), Value::Boolean(b)) => a == b,
+    (Value::String(a), Value::String(b)) => a == b,
+    _ => false,
+  }
+}
+
+/// `lhs op rhs`, for the operation at `src`.
+fn binary<'p>(
+  op: &Operation,
+  lhs: Value<'p>,
+  rhs: Value<'p>,
+  src: &Src,
+) -> Result<Value<'p>, RuntimeError> {
+  if op.is_comparison() {
+    let ordering = match (&lhs, &rhs) {
+      (Value::Number(a), Value::Number(b)) => a.partial_cmp(b),
+      (Value::Char(a), Value::Char(b)) => Some(a.cmp(b)),
+      _ => {
+        return Err(RuntimeError::Stuck(
+          format!("can't compare `{lhs}` and `{rhs}`"),
+          src.clone(),
+        ))
+      }
+    };
+    // `>=` and `<=` negate `<` and `>`, as in Bend.
+    let lt = ordering == Some(Ordering::Less);
+    let gt = ordering == Some(Ordering::Greater);
+    let eq = ordering == Some(Ordering::Equal);
+    return Ok(Value::Boolean(match op {
+      Operation::Gt => gt,
+      Operation::Gte => !lt,
+      Operation::Lt => lt,
+      Operation::Lte => !gt,
+      Operation::Eq => eq,
+      _ => !eq,
+    }));
+  }
+
+  match (op, lhs, rhs) {
+    (Operation::Concat, Value::String(a), Value::String(b)) => Ok(string(format!("{a}{b}"))),
+    (Operation::ConcatList, xs, ys) => Ok(list(elements(&xs), ys)),
+    (Operation::Cons, head, tail) => Ok(Value::Cons(Rc::new((head, tail)))),
+    (_, Value::Number(a), Value::Number(b)) => arithmetic(op, a, b, src).map(Value::Number),
+    (_, lhs, rhs) => Err(RuntimeError::Stuck(
+      format!("bad operands `{lhs}` and `{rhs}`"),
+      src.clone(),
+    )),
+  }
+}
+
+fn arithmetic(op: &Operation, a: Number, b: Number, src: &Src) -> Result<Number, RuntimeError> {
+  let stuck = |reason: &str| Err(RuntimeError::Stuck(reason.to_string(), src.clone()));
+  let by_zero = || RuntimeError::DivisionByZero(src.clone());
+  let n = match (a, b) {
+    (Number::U24(a), Number::U24(b)) => match op {
+      Operation::Add => u24(a.wrapping_add(b)),
+      Operation::Sub => u24(a.wrapping_sub(b)),
+      Operation::Mul => u24(a.wrapping_mul(b)),
+      Operation::Div => u24(a.checked_div(b).ok_or_else(by_zero)?),
+      Operation::Rem => u24(a.checked_rem(b).ok_or_else(by_zero)?),
+      Operation::Pow { .. } => u24(power(a, b)),
+      Operation::BitAnd => u24(a & b),
+      Operation::BitOr => u24(a | b),
+      Operation::BitXor => u24(a ^ b),
+      // HVM shifts by the amount's low five bits.
+      Operation::Shl { .. } => u24(a << (b & 31)),
+      Operation::Shr { .. } => u24(a >> (b & 31)),
+      _ => return stuck("not an arithmetic operation"),
+    },
+    (Number::I24(a), Number::I24(b)) => match op {
+      Operation::Add => i24(a.wrapping_add(b)),
+      Operation::Sub => i24(a.wrapping_sub(b)),
+      Operation::Mul => i24(a.wrapping_mul(b)),
+      Operation::Div => i24(a.checked_div(b).ok_or_else(by_zero)?),
+      Operation::Rem => i24(a.checked_rem(b).ok_or_else(by_zero)?),
+      // Negative exponents give one.
+      Operation::Pow { .. } if b < 1 => i24(1),
+      Operation::Pow { .. } => i24(power(a as u32, b as u32) as i32),
+      Operation::BitAnd => i24(a & b),
+      Operation::BitOr => i24(a | b),
+      Operation::BitXor => i24(a ^ b),
+      // Like u24 shifts, and `>>` is arithmetic.
+      Operation::Shl { .. } => i24(a << (b & 31)),
+      Operation::Shr { .. } => i24(a >> (b & 31)),
+      _ => return stuck("not an arithmetic operation"),
+    },
+    (Number::F24(a), Number::F24(b)) => Number::F24(match op {
+      Operation::Add => a + b,
+      Operation::Sub => a - b,
+      Operation::Mul => a * b,
+      Operation::Div => a / b,
+      Operation::Rem => a % b,
+      Operation::Pow { .. } => a.powf(b),
+      _ => return stuck("not a float operation"),
+    }),
+    (a, b) => return stuck(&format!("mixed operands {a:?} and {b:?}")),
+  };
+  Ok(n)
+}
+
+/// Exponentiation by squaring, wrapping like the multiplications it is
+/// made of.
+fn power(mut base: u32, mut exponent: u32) -> u32 {
+  let mut result = 1u32;
+  while exponent > 0 {
+    if exponent & 1 == 1 {
+      result = result.wrapping_mul(base);
+    }
+    base = base.wrapping_mul(base);
+    exponent >>= 1;
+  }
+  result
+}
+
+/// The chars of `s` from `start`, at most `length` of them.
+fn substring(s: &str, start: i32, length: i32) -> String {
+  s.chars()
+    .skip(start.max(0) as usize)
+    .take(length.max(0) as usize)
+    .collect()
+}
+
+/// Reads an optional `-` and then nothing but decimal digits, if they make
+/// an i24.
+fn string_to_number(s: &str) -> Option<i32> {
+  let digits = s.strip_prefix('-').unwrap_or(s);
+  if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+    return None;
+  }
+  let n = s.parse::<i32>().ok()?;
+  (-(1 << 23)..1 << 23).contains(&n).then_some(n)
+}
+
+/// Truncates, negative numbers giving zero and big ones the largest u24.
+fn f24_to_u24(x: f32) -> u32 {
+  (x as u32).min(U24_MASK)
+}
+
+impl fmt::Display for Number {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Number::U24(n) => write!(f, "{n}"),
+      Number::I24(n) => write!(f, "{n}"),
+      Number::F24(n) if n.fract() == 0.0 && n.is_finite() => write!(f, "{n}.0"),
+      Number::F24(n) => write!(f, "{n}"),
+    }
+  }
+}
+
+/// Values are written as in Oktu, functions as `<fun>`.
+impl fmt::Display for Value<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Value::Unit => write!(f, "()"),
+      Value::Number(n) => write!(f, "{n}"),
+      Value::Char(c) => write!(f, "{}", escape_char(*c)),
+      Value::Boolean(b) => write!(f, "{b}"),
+      Value::String(s) => write!(f, "{}", escape(s)),
+      Value::Tuple(elements) => match &**elements {
+        [single] => write!(f, "({single},)"),
+        elements => {
+          let elements = elements.iter().map(ToString::to_string).collect::<Vec<_>>();
+          write!(f, "({})", elements.join(", "))
+        }
+      },
+      Value::Nil | Value::Cons(_) => {
+        let elements = elements(self)
+          .iter()
+          .map(ToString::to_string)
+          .collect::<Vec<_>>();
+        write!(f, "[{}]", elements.join(", "))
+      }
+      Value::Variant(variant) => write!(f, ".{variant}"),
+      Value::Closure(_) | Value::Builtin { .. } => write!(f, "<fun>"),
+    }
+  }
+}
+
+impl Diagnostic for RuntimeError {
+  fn message(&self) -> String {
+    match self {
+      RuntimeError::Hole(name, _) => format!("Reached the hole '?{name}'."),
+      RuntimeError::DivisionByZero(_) => "Division by zero.".to_string(),
+      RuntimeError::NoMatch(value, _) => format!("No arm matches the value {value}."),
+      RuntimeError::InvalidChar(n, _) => format!("{n} is not the code point of a char."),
+      RuntimeError::Undefined(name) => format!("There is no definition `{name}`."),
+      RuntimeError::Output(error, _) => format!("Could not print: {error}"),
+      RuntimeError::Stuck(reason, _) => format!("The program went wrong: {reason}"),
+    }
+  }
+
+  fn severity(&self) -> Severity {
+    Severity::Error
+  }
+
+  fn code(&self) -> &'static str {
+    match self {
+      RuntimeError::Hole(..) => "reached-hole",
+      RuntimeError::DivisionByZero(_) => "division-by-zero",
+      RuntimeError::NoMatch(..) => "no-matching-arm",
+      RuntimeError::InvalidChar(..) => "invalid-char",
+      RuntimeError::Undefined(_) => "undefined",
+      RuntimeError::Output(..) => "output-error",
+      RuntimeError::Stuck(..) => "stuck",
+    }
+  }
+
+  fn extra(&self) -> Vec<String> {
+    match self {
+      RuntimeError::Stuck(..) => vec!["This is a bug in the type checker.".to_string()],
+      _ => vec![],
+    }
+  }
+
+  fn src(&self) -> Option<Src> {
+    match self {
+      RuntimeError::Hole(_, src)
+      | RuntimeError::DivisionByZero(src)
+      | RuntimeError::NoMatch(_, src)
+      | RuntimeError::InvalidChar(_, src)
+      | RuntimeError::Output(_, src)
+      | RuntimeError::Stuck(_, src) => Some(src.clone()),
+      RuntimeError::Undefined(_) => None,
+    }
+  }
+}
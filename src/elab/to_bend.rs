@@ -1,20 +1,62 @@
+use std::collections::VecDeque;
+
+use ::bend::{
+  diagnostics::{DiagnosticsConfig, Severity as BendSeverity},
+  CompileOpts, RunOpts,
+};
 use bend::fun as bend;
 
-use super::{Expression, Function, Literal, Operation, TopLevel};
+use super::{
+  Arm, Expression, ExpressionType, Function, Literal, Operation, Pattern, PatternType, TopLevel,
+  UnaryOperation,
+};
+use crate::{
+  ast::Src,
+  builtins,
+  checker::types::TypeKind,
+  report::{Diagnostic, Severity},
+};
+
+/// A program that type checked but could not be lowered to Bend.
+pub struct BackendError(pub String);
 
 const OKTU_BUILTINS_PATH: &str = "src/oktu.builtins.bend";
+const OKTU_BUILTINS: &str = include_str!("../oktu.builtins.bend");
+
+const OKTU_POW: &str = "_Oktu_/Number/pow";
+const OKTU_I24_SHL: &str = "_Oktu_/I24/shl";
+const OKTU_I24_SHR: &str = "_Oktu_/I24/shr";
 
-const OKTU_CONCAT: &str = "_Oktu_/String/concat";
+/// The definition HVM starts from.
+const ENTRYPOINT: &str = "main";
+/// The variable a match scrutinee is bound to. Like every name the compiler
+/// makes up, it has a `-`, so no Oktu variable can capture it.
+const SCRUTINEE: &str = "oktu-scrutinee";
+/// The variable a projected tuple element is bound to.
+const PROJECTED: &str = "oktu-element";
+/// The variable a tuple's first element may be bound to.
+const TUPLE_FIRST: &str = "oktu-first";
 
 impl Literal {
   pub fn to_bend(self) -> Result<bend::Term, String> {
     match self {
-      Literal::Number { value } => Ok(bend::Term::Num {
-        val: bend::Num::I24(value),
+      Literal::Integer { value, t } => {
+        let val = match &*t.resolve() {
+          TypeKind::U24 => bend::Num::U24(value as u32),
+          TypeKind::F24 => bend::Num::F24(value as f32),
+          _ => bend::Num::I24(value as i32),
+        };
+        Ok(bend::Term::Num { val })
+      }
+      Literal::Float { value } => Ok(bend::Term::Num {
+        val: bend::Num::F24(value),
       }),
       Literal::String { value } => Ok(bend::Term::Str {
         val: bend::STRINGS.get(value),
       }),
+      Literal::Char { value } => Ok(bend::Term::Num {
+        val: bend::Num::U24(value as u32),
+      }),
       Literal::Boolean { value } => Ok(bend::Term::Num {
         val: bend::Num::U24(if value { 1 } else { 0 }),
       }),
@@ -24,30 +66,28 @@ impl Literal {
 
 impl Expression {
   pub fn to_bend(self) -> Result<bend::Term, String> {
-    match self {
-      Expression::Error { message } => Err(message),
-      Expression::Unit => Ok(bend::Term::Era),
-      Expression::Hole { name } => Err(format!("Hole '{name}'")),
-      Expression::Variable { name } => Ok(bend::Term::Var {
-        nam: bend::Name::new(name),
-      }),
-      Expression::Fun { variable, body } => Ok(bend::Term::Lam {
+    match *self.data {
+      ExpressionType::Error { message } => Err(message),
+      ExpressionType::Unit => Ok(bend::Term::Era),
+      ExpressionType::Hole { name } => Err(format!("Hole '{name}'")),
+      ExpressionType::Variable { name, .. } => Ok(var(name)),
+      ExpressionType::Fun { parameter, body } => Ok(bend::Term::Lam {
         tag: bend::Tag::Auto,
-        pat: bend::Pattern::Var(Some(bend::Name::new(variable))).into(),
+        pat: irrefutable(parameter)?.into(),
         bod: body.to_bend()?.into(),
       }),
-      Expression::Application { function, argument } => Ok(bend::Term::App {
+      ExpressionType::Application { function, argument } => Ok(bend::Term::App {
         tag: bend::Tag::Auto,
         fun: function.to_bend()?.into(),
         arg: argument.to_bend()?.into(),
       }),
-      Expression::Literal { literal } => literal.to_bend(),
-      Expression::Let { bind, value, next } => Ok(bend::Term::Let {
-        pat: bend::Pattern::Var(Some(bend::Name::new(bind))).into(),
+      ExpressionType::Literal { literal } => literal.to_bend(),
+      ExpressionType::Let { bind, value, next } => Ok(bend::Term::Let {
+        pat: irrefutable(bind)?.into(),
         val: value.to_bend()?.into(),
         nxt: next.to_bend()?.into(),
       }),
-      Expression::If {
+      ExpressionType::If {
         condition,
         then,
         otherwise,
@@ -59,8 +99,12 @@ impl Expression {
         pred: None,
         arms: vec![otherwise.to_bend()?, then.to_bend()?],
       }),
-      Expression::Match { .. } => Err("Match is not implemented".to_string()),
-      Expression::BinaryOp { op, lhs, rhs } => {
+      ExpressionType::Match { scrutinee, arms } => Ok(bend::Term::Let {
+        pat: bend::Pattern::Var(Some(bend::Name::new(SCRUTINEE))).into(),
+        val: scrutinee.to_bend()?.into(),
+        nxt: arms_to_bend(arms.into())?.into(),
+      }),
+      ExpressionType::BinaryOp { op, lhs, rhs } => {
         let fst = lhs.to_bend()?.into();
         let snd = rhs.to_bend()?.into();
         match op {
@@ -84,18 +128,89 @@ impl Expression {
             fst,
             snd,
           }),
+          Operation::Rem => Ok(bend::Term::Oper {
+            opr: bend::Op::REM,
+            fst,
+            snd,
+          }),
+          // HVM only has exponentiation of floats.
+          Operation::Pow { t } => match &*t.resolve() {
+            TypeKind::F24 => Ok(bend::Term::Oper {
+              opr: bend::Op::POW,
+              fst,
+              snd,
+            }),
+            _ => Ok(bend::Term::call(bend::Term::r#ref(OKTU_POW), [*fst, *snd])),
+          },
+          Operation::BitAnd => Ok(bend::Term::Oper {
+            opr: bend::Op::AND,
+            fst,
+            snd,
+          }),
+          Operation::BitOr => Ok(bend::Term::Oper {
+            opr: bend::Op::OR,
+            fst,
+            snd,
+          }),
+          Operation::BitXor => Ok(bend::Term::Oper {
+            opr: bend::Op::XOR,
+            fst,
+            snd,
+          }),
+          // HVM only shifts u24, i24 shifts go through it.
+          Operation::Shl { t } => match &*t.resolve() {
+            TypeKind::U24 => Ok(bend::Term::Oper {
+              opr: bend::Op::SHL,
+              fst,
+              snd,
+            }),
+            _ => Ok(bend::Term::call(
+              bend::Term::r#ref(OKTU_I24_SHL),
+              [*fst, *snd],
+            )),
+          },
+          Operation::Shr { t } => match &*t.resolve() {
+            TypeKind::U24 => Ok(bend::Term::Oper {
+              opr: bend::Op::SHR,
+              fst,
+              snd,
+            }),
+            _ => Ok(bend::Term::call(
+              bend::Term::r#ref(OKTU_I24_SHR),
+              [*fst, *snd],
+            )),
+          },
           Operation::Gt => Ok(bend::Term::Oper {
             opr: bend::Op::GT,
             fst,
             snd,
           }),
-          Operation::Gte => todo!(),
+          // Bend has no `>=` nor `<=`, so they negate `<` and `>`.
+          Operation::Gte => Ok(bend::Term::Oper {
+            opr: bend::Op::EQ,
+            fst: bend::Term::Oper {
+              opr: bend::Op::LT,
+              fst,
+              snd,
+            }
+            .into(),
+            snd: Literal::Boolean { value: false }.to_bend()?.into(),
+          }),
           Operation::Lt => Ok(bend::Term::Oper {
             opr: bend::Op::LT,
             fst,
             snd,
           }),
-          Operation::Lte => todo!(),
+          Operation::Lte => Ok(bend::Term::Oper {
+            opr: bend::Op::EQ,
+            fst: bend::Term::Oper {
+              opr: bend::Op::GT,
+              fst,
+              snd,
+            }
+            .into(),
+            snd: Literal::Boolean { value: false }.to_bend()?.into(),
+          }),
           Operation::Eq => Ok(bend::Term::Oper {
             opr: bend::Op::EQ,
             fst,
@@ -107,19 +222,224 @@ impl Expression {
             snd,
           }),
           Operation::Concat => Ok(bend::Term::call(
-            bend::Term::r#ref(OKTU_CONCAT),
+            bend::Term::r#ref(builtins::get("string_concat").bend),
+            [*fst, *snd],
+          )),
+          Operation::ConcatList => Ok(bend::Term::call(
+            bend::Term::r#ref(builtins::get("list_concat").bend),
+            [*fst, *snd],
+          )),
+          // Short-circuits, the right side only runs when it decides the
+          // result.
+          Operation::And => Ok(bend::Term::Swt {
+            bnd: None,
+            arg: fst,
+            with_bnd: vec![],
+            with_arg: vec![],
+            pred: None,
+            arms: vec![Literal::Boolean { value: false }.to_bend()?, *snd],
+          }),
+          Operation::Or => Ok(bend::Term::Swt {
+            bnd: None,
+            arg: fst,
+            with_bnd: vec![],
+            with_arg: vec![],
+            pred: None,
+            arms: vec![*snd, Literal::Boolean { value: true }.to_bend()?],
+          }),
+          Operation::Cons => Ok(bend::Term::call(
+            bend::Term::r#ref("List/Cons"),
             [*fst, *snd],
           )),
         }
       }
-      Expression::Variant { variant: _ } => todo!(),
-      Expression::Tuple { elements } => Ok(bend::Term::Fan {
-        fan: bend::FanKind::Tup,
-        tag: bend::Tag::Auto,
-        els: elements.into_iter().flat_map(Self::to_bend).collect(),
+      ExpressionType::UnaryOp { op, operand } => match op {
+        UnaryOperation::Neg { t } => Ok(bend::Term::Oper {
+          opr: bend::Op::SUB,
+          fst: Literal::Integer { value: 0, t }.to_bend()?.into(),
+          snd: operand.to_bend()?.into(),
+        }),
+        UnaryOperation::Not => Ok(bend::Term::Oper {
+          opr: bend::Op::EQ,
+          fst: operand.to_bend()?.into(),
+          snd: Literal::Boolean { value: false }.to_bend()?.into(),
+        }),
+      },
+      ExpressionType::Variant { .. } => Err("Variants are not implemented".to_string()),
+      ExpressionType::List { elements } => Ok(bend::Term::List {
+        els: elements
+          .into_iter()
+          .map(Self::to_bend)
+          .collect::<Result<_, _>>()?,
       }),
+      ExpressionType::Tuple { elements } => {
+        let mut els = elements
+          .into_iter()
+          .map(Self::to_bend)
+          .collect::<Result<Vec<_>, _>>()?;
+        // Bend reads `(+1, ...)` back as an addition, so a signed number
+        // in front is bound to a variable first.
+        let signed = matches!(
+          els.first(),
+          Some(bend::Term::Num {
+            val: bend::Num::I24(_) | bend::Num::F24(_)
+          })
+        );
+        let first = signed.then(|| std::mem::replace(&mut els[0], var(TUPLE_FIRST)));
+        let tuple = bend::Term::Fan {
+          fan: bend::FanKind::Tup,
+          tag: bend::Tag::Static,
+          els,
+        };
+        match first {
+          Some(first) => Ok(bend::Term::Let {
+            pat: bend::Pattern::Var(Some(bend::Name::new(TUPLE_FIRST))).into(),
+            val: first.into(),
+            nxt: tuple.into(),
+          }),
+          None => Ok(tuple),
+        }
+      }
+      ExpressionType::Projection { tuple, index, size } => {
+        let binds = (0..size)
+          .map(|i| match i == index {
+            true => bend::Pattern::Var(Some(bend::Name::new(PROJECTED))),
+            false => bend::Pattern::Var(None),
+          })
+          .collect();
+        Ok(bend::Term::Let {
+          pat: bend::Pattern::Fan(bend::FanKind::Tup, bend::Tag::Static, binds).into(),
+          val: tuple.to_bend()?.into(),
+          nxt: var(PROJECTED).into(),
+        })
+      }
+    }
+  }
+}
+
+fn var(name: impl Into<String>) -> bend::Term {
+  bend::Term::Var {
+    nam: bend_name(name.into()),
+  }
+}
+
+/// `name` as a Bend name, which only takes ASCII letters, digits and `_./-`.
+/// Primes become `-p` and other characters `-x<code>-`, since Oktu names
+/// can't contain `-`.
+fn bend_name(name: String) -> bend::Name {
+  if name.chars().all(bend::parser::is_name_char) {
+    return bend::Name::new(name);
+  }
+  let mut mangled = String::new();
+  for c in name.chars() {
+    match c {
+      '\'' => mangled.push_str("-p"),
+      c if bend::parser::is_name_char(c) => mangled.push(c),
+      c => mangled.push_str(&format!("-x{:x}-", c as u32)),
     }
   }
+  bend::Name::new(mangled)
+}
+
+/// A pattern that always matches, as a Bend pattern. Tuples become fans.
+fn irrefutable(pattern: Pattern) -> Result<bend::Pattern, String> {
+  match *pattern.data {
+    PatternType::Wildcard => Ok(bend::Pattern::Var(None)),
+    PatternType::Variable { name } => Ok(bend::Pattern::Var(Some(bend_name(name)))),
+    PatternType::Tuple { elements } => Ok(bend::Pattern::Fan(
+      bend::FanKind::Tup,
+      bend::Tag::Static,
+      elements
+        .into_iter()
+        .map(irrefutable)
+        .collect::<Result<_, _>>()?,
+    )),
+    _ => Err("Nested patterns are not implemented".to_string()),
+  }
+}
+
+/// Binds the variables of `pattern` to `value` in `next`.
+fn bind(pattern: Pattern, value: bend::Term, next: bend::Term) -> Result<bend::Term, String> {
+  if let PatternType::Wildcard = *pattern.data {
+    return Ok(next);
+  }
+  Ok(bend::Term::Let {
+    pat: irrefutable(pattern)?.into(),
+    val: value.into(),
+    nxt: next.into(),
+  })
+}
+
+/// Lowers match arms, tried in order, against the value bound to
+/// [`SCRUTINEE`].
+fn arms_to_bend(mut arms: VecDeque<Arm>) -> Result<bend::Term, String> {
+  let Some(Arm { left, right }) = arms.pop_front() else {
+    return Ok(bend::Term::Era);
+  };
+
+  match *left.data {
+    PatternType::Wildcard | PatternType::Variable { .. } | PatternType::Tuple { .. } => {
+      bind(left, var(SCRUTINEE), right.to_bend()?)
+    }
+    PatternType::Literal {
+      literal: Literal::String { .. },
+    } => Err("Matching on strings is not implemented".to_string()),
+    PatternType::Literal { literal } => Ok(bend::Term::Swt {
+      bnd: None,
+      arg: bend::Term::Oper {
+        opr: bend::Op::EQ,
+        fst: var(SCRUTINEE).into(),
+        snd: literal.to_bend()?.into(),
+      }
+      .into(),
+      with_bnd: vec![],
+      with_arg: vec![],
+      pred: None,
+      arms: vec![arms_to_bend(arms)?, right.to_bend()?],
+    }),
+    PatternType::Nil | PatternType::Cons { .. } => {
+      // Consecutive list arms become one match, the first arm for each
+      // constructor wins and missing ones fall through to the next arms.
+      // Bend binds the fields of `List/Cons` as `<scrutinee>.head` and
+      // `<scrutinee>.tail`.
+      arms.push_front(Arm { left, right });
+      let mut nil = None;
+      let mut cons = None;
+      while let Some(Arm { left, .. }) = arms.front() {
+        if !matches!(*left.data, PatternType::Nil | PatternType::Cons { .. }) {
+          break;
+        }
+        let Arm { left, right } = arms.pop_front().unwrap();
+        match *left.data {
+          PatternType::Nil if nil.is_none() => nil = Some(right.to_bend()?),
+          PatternType::Cons { head, tail } if cons.is_none() => {
+            let body = bind(*tail, var(format!("{SCRUTINEE}.tail")), right.to_bend()?)?;
+            cons = Some(bind(*head, var(format!("{SCRUTINEE}.head")), body)?);
+          }
+          _ => {}
+        }
+      }
+      let (nil, cons) = match (nil, cons) {
+        (Some(nil), Some(cons)) => (nil, cons),
+        (nil, cons) => {
+          let rest = arms_to_bend(arms)?;
+          (nil.unwrap_or(rest.clone()), cons.unwrap_or(rest))
+        }
+      };
+      Ok(bend::Term::Mat {
+        bnd: Some(bend::Name::new(SCRUTINEE)),
+        arg: var(SCRUTINEE).into(),
+        with_bnd: vec![],
+        with_arg: vec![],
+        arms: vec![
+          (Some(bend::Name::new("List/Nil")), vec![], nil),
+          (Some(bend::Name::new("List/Cons")), vec![], cons),
+        ],
+      })
+    }
+    PatternType::Variant { .. } => Err("Matching on variants is not implemented".to_string()),
+    PatternType::Error { message } => Err(message),
+  }
 }
 
 pub enum BendTopLevel {
@@ -144,7 +464,7 @@ impl Function {
       body: self.body.to_bend()?,
     }];
     Ok(bend::Definition {
-      name: bend::Name::new(name),
+      name: bend_name(name),
       rules,
       builtin: false,
     })
@@ -154,9 +474,13 @@ impl Function {
 impl super::Program {
   pub fn to_bend(self) -> Result<bend::Book, String> {
     let oktu_builtins_path = std::path::Path::new(OKTU_BUILTINS_PATH);
-    let code = std::fs::read_to_string(oktu_builtins_path).map_err(|e| e.to_string())?;
     let mut book =
-      bend::load_book::do_parse_book(&code, oktu_builtins_path, bend::Book::default())?;
+      bend::load_book::do_parse_book(OKTU_BUILTINS, oktu_builtins_path, bend::Book::default())?;
+    for def in book.defs.values_mut() {
+      for rule in def.rules.iter_mut() {
+        drop_switch_preds(&mut rule.body);
+      }
+    }
     for decl in self.declarations {
       match decl.to_bend()? {
         BendTopLevel::Definition(def) => _ = book.defs.insert(def.name.clone(), def),
@@ -166,3 +490,85 @@ impl super::Program {
     Ok(book)
   }
 }
+
+/// Runs a compiled program with HVM from the definition `entrypoint`,
+/// returning the term it reduces to. The program can't have a `main` of its
+/// own.
+pub fn run(mut book: bend::Book, entrypoint: &str) -> Result<bend::Term, String> {
+  // Bend floats closed terms out of every definition but `main`, and HVM
+  // leaves them unevaluated in results, so `main` is a copy of the
+  // definition that plain references lead to.
+  let mut name = bend_name(entrypoint.to_string());
+  let rules = loop {
+    let Some(definition) = book.defs.get(&name) else {
+      return Err(format!("There is no definition `{entrypoint}`"));
+    };
+    match definition.rules.as_slice() {
+      [bend::Rule {
+        pats,
+        body: bend::Term::Var { nam },
+      }] if pats.is_empty() && book.defs.contains_key(nam) => name = nam.clone(),
+      rules => break rules.to_vec(),
+    }
+  };
+  let main = bend::Definition {
+    name: bend::Name::new(ENTRYPOINT),
+    rules,
+    builtin: false,
+  };
+  book.defs.insert(main.name.clone(), main);
+
+  // The book goes through Bend's parser, as `bend run` would, to get its
+  // builtins and the shape the compiler expects.
+  let code = book.display_pretty().to_string();
+  let path = std::path::Path::new("<oktu>");
+  let book = bend::load_book::do_parse_book(&code, path, bend::Book::builtins())?;
+
+  let diagnostics = DiagnosticsConfig::new(BendSeverity::Allow, false);
+  let result = ::bend::run_book(
+    book,
+    RunOpts::default(),
+    CompileOpts::default(),
+    diagnostics,
+    None,
+    "run",
+  );
+  match result {
+    Ok(Some((term, _, _))) => Ok(term),
+    Ok(None) => Err("HVM returned nothing".to_string()),
+    Err(diagnostics) => Err(diagnostics.to_string()),
+  }
+}
+
+/// Bend prints the predecessor of a parsed `switch` as `_ n-1:`, which it
+/// cannot parse back. The builtins never use it, so it is dropped.
+fn drop_switch_preds(term: &mut bend::Term) {
+  if let bend::Term::Swt { pred, .. } = term {
+    *pred = None;
+  }
+  for child in term.children_mut() {
+    drop_switch_preds(child);
+  }
+}
+
+impl Diagnostic for BackendError {
+  fn message(&self) -> String {
+    format!("Could not compile to Bend: {}", self.0)
+  }
+
+  fn severity(&self) -> Severity {
+    Severity::Error
+  }
+
+  fn code(&self) -> &'static str {
+    "backend-error"
+  }
+
+  fn extra(&self) -> Vec<String> {
+    vec![]
+  }
+
+  fn src(&self) -> Option<Src> {
+    None
+  }
+}
@@ -0,0 +1,676 @@
+pub mod doc;
+
+use std::collections::VecDeque;
+
+use self::doc::{concat, group, join, nest, render, text, Doc};
+use crate::{
+  ast::{
+    Arm, Assoc, Enum, Expression, ExpressionType, FixityDecl, Function, Import, Literal, Pattern,
+    PatternType, Program, Src, TopLevel, UnaryOperation,
+  },
+  parse::{self, comments::Comment},
+  report::{Diagnostic, Reporter, Severity},
+  source::FileId,
+};
+
+/// The column lines are kept within where possible.
+const WIDTH: usize = 80;
+const INDENT: usize = 2;
+
+pub enum FormatError {
+  /// The formatted program didn't parse back to the same one, or wasn't
+  /// formatted itself.
+  Unstable(Src),
+}
+
+/// Formats the program in `source`, checking that the result parses back to
+/// the same program and comments and formats to itself. Returns `None`,
+/// after reporting why, when the program has syntax errors or the check
+/// fails.
+pub fn format_source(file: FileId, source: &str, reporter: &Reporter) -> Option<String> {
+  let errors = reporter.counts().errors;
+  let (program, comments) = parse::parse_with_comments(file, source, reporter);
+  if reporter.counts().errors > errors {
+    return None;
+  }
+  let formatted = format(&program, &comments, source);
+
+  let (check, _diagnostics) = Reporter::new();
+  let (reparsed, reparsed_comments) = parse::parse_with_comments(file, &formatted, &check);
+  let texts = |comments: &[Comment]| comments.iter().map(|c| c.text.clone()).collect::<Vec<_>>();
+  let stable = !check.has_errors()
+    && spanless(&reparsed) == spanless(&program)
+    && texts(&reparsed_comments) == texts(&comments)
+    && format(&reparsed, &reparsed_comments, &formatted) == formatted;
+  if !stable {
+    reporter.report(FormatError::Unstable(Src::new(file, 0, 0)));
+    return None;
+  }
+  Some(formatted)
+}
+
+/// A declaration or a comment between declarations.
+enum Item<'a> {
+  Import(&'a Import),
+  Fixity(&'a FixityDecl),
+  TopLevel(&'a TopLevel),
+  Comment(&'a Comment),
+}
+
+impl Item<'_> {
+  fn src(&self) -> &Src {
+    match self {
+      Item::Import(import) => &import.src,
+      Item::Fixity(fixity) => &fixity.src,
+      Item::TopLevel(TopLevel::Function(function)) => &function.src,
+      Item::TopLevel(TopLevel::Enum(enum_)) => &enum_.src,
+      Item::Comment(comment) => &comment.src,
+    }
+  }
+}
+
+/// Formats `program`, parsed from `source` with `comments`.
+///
+/// Declarations keep their order and up to one blank line between them.
+/// Comments inside a declaration go on their own line before what follows
+/// them, and those at its end after it.
+pub fn format(program: &Program, comments: &[Comment], source: &str) -> String {
+  let mut items: Vec<Item> = program
+    .imports
+    .iter()
+    .map(Item::Import)
+    .chain(program.fixities.iter().map(Item::Fixity))
+    .chain(program.declarations.iter().map(Item::TopLevel))
+    .collect();
+  let (inner, outer): (Vec<&Comment>, Vec<&Comment>) = comments.iter().partition(|comment| {
+    items.iter().any(|item| {
+      let range = &item.src().range;
+      range.start < comment.src.range.start && comment.src.range.end <= range.end
+    })
+  });
+  items.extend(outer.into_iter().map(Item::Comment));
+  items.sort_by_key(|item| item.src().range.start);
+
+  let mut printer = Printer {
+    source,
+    comments: inner.into(),
+  };
+  let mut out = String::new();
+  let mut previous_end = None;
+  for item in items {
+    let src = item.src().clone();
+    if let Some(end) = previous_end {
+      let between = &source[end..src.range.start];
+      if matches!(item, Item::Comment(_)) && !between.contains('\n') {
+        out.push(' ');
+      } else {
+        out.push('\n');
+        if between.matches('\n').count() > 1 {
+          out.push('\n');
+        }
+      }
+    }
+    out.push_str(&render(&printer.item(item), WIDTH));
+
+    let mut trailing = " ";
+    while let Some(comment) = printer.comments.pop_front() {
+      if comment.src.range.start >= src.range.end {
+        printer.comments.push_front(comment);
+        break;
+      }
+      out.push_str(trailing);
+      out.push_str(&comment.text);
+      trailing = "\n";
+    }
+    previous_end = Some(src.range.end);
+  }
+  out.push('\n');
+  out
+}
+
+/// Where an expression is printed, deciding whether it needs parentheses.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Context {
+  /// Directly inside parentheses, as a tuple element.
+  Paren,
+  /// The operand of a section, which can't be a block.
+  SectionOperand,
+  /// Anywhere an expression is expected.
+  Expression,
+  /// An operand of an infix operator.
+  Operand,
+  /// A function being applied, or the operand of a prefix operator.
+  Application,
+  /// An argument of an application.
+  Argument,
+}
+
+fn needs_parens(expression: &Expression, context: Context) -> bool {
+  let data = &*expression.data;
+  let block = matches!(
+    data,
+    ExpressionType::Fun { .. }
+      | ExpressionType::Let { .. }
+      | ExpressionType::If { .. }
+      | ExpressionType::Match { .. }
+  );
+  let chain = matches!(data, ExpressionType::Infix { .. });
+  // `|` also separates match arms, so it is only an operator in parentheses.
+  let pipe = matches!(data, ExpressionType::Infix { rest, .. } if rest.iter().any(|(op, _)| *op.data == "|"));
+  let operand = match data {
+    ExpressionType::UnaryOp { .. } => true,
+    ExpressionType::Literal {
+      literal: Literal::Integer { value },
+    } => *value < 0,
+    ExpressionType::Literal {
+      literal: Literal::Float { value },
+    } => value.is_sign_negative(),
+    _ => false,
+  };
+  let application = matches!(data, ExpressionType::Application { .. });
+  match context {
+    Context::Paren => false,
+    Context::SectionOperand => block,
+    Context::Expression => pipe,
+    Context::Operand => block || chain,
+    Context::Application => block || chain || operand,
+    Context::Argument => block || chain || operand || application,
+  }
+}
+
+struct Printer<'a> {
+  source: &'a str,
+  /// The comments inside declarations not printed yet, in order.
+  comments: VecDeque<&'a Comment>,
+}
+
+impl Printer<'_> {
+  fn item(&mut self, item: Item) -> Doc {
+    match item {
+      Item::Import(import) => self.import(import),
+      Item::Fixity(fixity) => self.fixity(fixity),
+      Item::TopLevel(TopLevel::Function(function)) => self.function(function),
+      Item::TopLevel(TopLevel::Enum(enum_)) => self.enum_(enum_),
+      Item::Comment(comment) => text(comment.text.clone()),
+    }
+  }
+
+  fn import(&mut self, import: &Import) -> Doc {
+    let names = match &import.names {
+      Some(names) => {
+        let names = names.iter().map(|n| name(n)).collect::<Vec<_>>();
+        format!(".{{{}}}", names.join(", "))
+      }
+      None => String::new(),
+    };
+    text(format!("import {}{names}", import.module))
+  }
+
+  fn fixity(&mut self, fixity: &FixityDecl) -> Doc {
+    let assoc = match fixity.fixity.assoc {
+      Assoc::Left => "infixl",
+      Assoc::Right => "infixr",
+      Assoc::None => "infix",
+    };
+    let precedence = fixity.fixity.precedence;
+    text(format!("{assoc} {precedence} {}", fixity.operator))
+  }
+
+  fn function(&mut self, function: &Function) -> Doc {
+    let mut head = String::new();
+    if function.public {
+      head.push_str("pub ");
+    }
+    head.push_str("let ");
+    if function.rec {
+      head.push_str("rec ");
+    }
+    head.push_str(&name(&function.name));
+    for parameter in &function.parameters {
+      head.push(' ');
+      head.push_str(&parameter.data);
+    }
+    head.push_str(" :=");
+    let body = self.expression(&function.body, Context::Expression);
+    group(concat([
+      text(head),
+      nest(INDENT, concat([Doc::Line, body])),
+    ]))
+  }
+
+  fn enum_(&mut self, enum_: &Enum) -> Doc {
+    let public = if enum_.public { "pub " } else { "" };
+    let variants = enum_.variants.iter().map(|v| text(format!(".{}", v.data)));
+    group(concat([
+      text(format!("{public}enum {} :=", enum_.name)),
+      nest(
+        INDENT,
+        concat([Doc::Line, join(variants, concat([text(","), Doc::Line]))]),
+      ),
+    ]))
+  }
+
+  /// The comments before `src`, each on its own line.
+  fn comments_before(&mut self, src: &Src) -> Doc {
+    let mut docs = Vec::new();
+    while let Some(comment) = self.comments.pop_front() {
+      if comment.src.range.start >= src.range.start {
+        self.comments.push_front(comment);
+        break;
+      }
+      docs.push(text(comment.text.clone()));
+      docs.push(Doc::HardLine);
+    }
+    concat(docs)
+  }
+
+  fn expression(&mut self, expression: &Expression, context: Context) -> Doc {
+    let comments = self.comments_before(&expression.src);
+    let doc = match needs_parens(expression, context) {
+      true => concat([text("("), self.bare(expression), text(")")]),
+      false => self.bare(expression),
+    };
+    concat([comments, doc])
+  }
+
+  /// `expression` without parentheses around it.
+  fn bare(&mut self, expression: &Expression) -> Doc {
+    match &*expression.data {
+      ExpressionType::Unit => text("()"),
+      ExpressionType::Hole { name } => text(format!("?{name}")),
+      ExpressionType::Variable { name } => text(name.clone()),
+      ExpressionType::Literal { literal } => self.literal(literal, &expression.src),
+      ExpressionType::Interpolation { .. } => self.written(&expression.src),
+      ExpressionType::Fun { parameters, body } => {
+        let parameters = parameters
+          .iter()
+          .map(|p| self.pattern(p))
+          .collect::<Vec<_>>();
+        group(concat([
+          text("fun "),
+          join(parameters, text(" ")),
+          text(" ->"),
+          nest(
+            INDENT,
+            concat([Doc::Line, self.expression(body, Context::Expression)]),
+          ),
+        ]))
+      }
+      ExpressionType::Application { .. } => {
+        let mut arguments = Vec::new();
+        let mut function = expression;
+        while let ExpressionType::Application {
+          function: inner,
+          argument,
+        } = &*function.data
+        {
+          arguments.push(argument);
+          function = inner;
+        }
+        let function = self.expression(function, Context::Application);
+        let arguments = arguments
+          .into_iter()
+          .rev()
+          .map(|argument| concat([Doc::Line, self.expression(argument, Context::Argument)]))
+          .collect::<Vec<_>>();
+        group(concat([function, nest(INDENT, concat(arguments))]))
+      }
+      ExpressionType::Let { .. } => self.let_chain(expression),
+      ExpressionType::If { .. } => self.if_chain(expression),
+      ExpressionType::Match { scrutinee, arms } => {
+        let scrutinee = self.expression(scrutinee, Context::Expression);
+        let arms = arms
+          .iter()
+          .map(|arm| concat([Doc::Line, self.arm(arm)]))
+          .collect::<Vec<_>>();
+        group(concat([
+          text("match "),
+          scrutinee,
+          text(" with"),
+          concat(arms),
+          Doc::Line,
+          text("end"),
+        ]))
+      }
+      ExpressionType::Infix { first, rest } => {
+        let first = self.expression(first, Context::Operand);
+        let rest = rest
+          .iter()
+          .map(|(op, operand)| {
+            concat([
+              Doc::Line,
+              text(format!("{} ", op.data)),
+              self.expression(operand, Context::Operand),
+            ])
+          })
+          .collect::<Vec<_>>();
+        group(concat([first, nest(INDENT, concat(rest))]))
+      }
+      ExpressionType::Section {
+        operator,
+        left,
+        right,
+      } => {
+        let op = &*operator.data;
+        match (left, right) {
+          (None, None) => text(format!("({op})")),
+          (Some(left), None) => concat([
+            text("("),
+            self.expression(left, Context::SectionOperand),
+            text(format!(" {op})")),
+          ]),
+          // `(*` and a space opens a comment, so the operand follows
+          // directly, in parentheses unless it is a single term.
+          (None, Some(right)) if op.starts_with('*') => concat([
+            text(format!("({op}")),
+            self.expression(right, Context::Argument),
+            text(")"),
+          ]),
+          (None, Some(right)) => concat([
+            text(format!("({op} ")),
+            self.expression(right, Context::SectionOperand),
+            text(")"),
+          ]),
+          (Some(_), Some(_)) => unreachable!("sections miss an operand"),
+        }
+      }
+      ExpressionType::UnaryOp { op, operand } => {
+        let op = match op {
+          UnaryOperation::Neg => "-",
+          UnaryOperation::Not => "not ",
+        };
+        concat([text(op), self.expression(operand, Context::Application)])
+      }
+      ExpressionType::Variant { variant } => text(format!(".{variant}")),
+      ExpressionType::Tuple { elements } => {
+        let trailing = if elements.len() == 1 { "," } else { "" };
+        let elements = elements
+          .iter()
+          .map(|e| self.expression(e, Context::Paren))
+          .collect::<Vec<_>>();
+        let elements = concat([
+          join(elements, concat([text(","), Doc::Line])),
+          text(trailing),
+        ]);
+        delimited("(", elements, ")")
+      }
+      ExpressionType::List { elements } => {
+        let elements = elements
+          .iter()
+          .map(|e| self.expression(e, Context::Expression))
+          .collect::<Vec<_>>();
+        delimited("[", join(elements, concat([text(","), Doc::Line])), "]")
+      }
+      ExpressionType::Projection { tuple, index } => {
+        // `1.0` would be a float.
+        let tuple = match &*tuple.data {
+          ExpressionType::Literal {
+            literal: Literal::Integer { .. } | Literal::Float { .. },
+          } => concat([text("("), self.expression(tuple, Context::Paren), text(")")]),
+          _ => self.expression(tuple, Context::Argument),
+        };
+        concat([tuple, text(format!(".{index}"))])
+      }
+      ExpressionType::BinaryOp { .. } => {
+        unreachable!("programs are formatted before their operators are resolved")
+      }
+    }
+  }
+
+  /// `let ... in` chains, broken all at once.
+  fn let_chain(&mut self, mut expression: &Expression) -> Doc {
+    let mut docs = Vec::new();
+    while let ExpressionType::Let { bind, value, next } = &*expression.data {
+      let bind = self.pattern(bind);
+      let value = self.expression(value, Context::Expression);
+      docs.push(group(concat([
+        text("let "),
+        bind,
+        text(" ="),
+        nest(INDENT, concat([Doc::Line, value])),
+        Doc::Line,
+        text("in"),
+      ])));
+      docs.push(Doc::Line);
+      docs.push(self.comments_before(&next.src));
+      expression = next;
+    }
+    docs.push(self.bare(expression));
+    group(concat(docs))
+  }
+
+  /// `if ... else if ... else` chains, broken all at once.
+  fn if_chain(&mut self, mut expression: &Expression) -> Doc {
+    let mut docs = Vec::new();
+    while let ExpressionType::If {
+      condition,
+      then,
+      otherwise,
+    } = &*expression.data
+    {
+      let condition = self.expression(condition, Context::Expression);
+      let then = self.expression(then, Context::Expression);
+      docs.extend([
+        text("if "),
+        condition,
+        text(" then"),
+        nest(INDENT, concat([Doc::Line, then])),
+        Doc::Line,
+        text("else"),
+      ]);
+      let comments = self.comments_before(&otherwise.src);
+      if let ExpressionType::If { .. } = &*otherwise.data {
+        docs.extend([text(" "), comments]);
+        expression = otherwise;
+        continue;
+      }
+      let otherwise = self.expression(otherwise, Context::Expression);
+      docs.push(nest(INDENT, concat([Doc::Line, comments, otherwise])));
+      break;
+    }
+    group(concat(docs))
+  }
+
+  fn arm(&mut self, arm: &Arm) -> Doc {
+    let comments = self.comments_before(&arm.left.src);
+    let left = self.pattern(&arm.left);
+    let right = self.expression(&arm.right, Context::Expression);
+    concat([
+      comments,
+      text("| "),
+      left,
+      text(" =>"),
+      group(nest(INDENT, concat([Doc::Line, right]))),
+    ])
+  }
+
+  /// Literals are kept as written: numbers with their base and separators,
+  /// strings and chars with their escapes and raw delimiters.
+  fn literal(&self, literal: &Literal, src: &Src) -> Doc {
+    match literal {
+      Literal::Integer { .. } | Literal::Float { .. } => {
+        // A negative number is a `-` and a literal, maybe with spaces.
+        let written = &self.source[src.range.clone()];
+        match written.strip_prefix('-') {
+          Some(digits) => text(format!("-{}", digits.trim_start())),
+          None => text(written),
+        }
+      }
+      Literal::String { .. } | Literal::Char { .. } => self.written(src),
+      Literal::Boolean { value } => text(value.to_string()),
+    }
+  }
+
+  fn written(&self, src: &Src) -> Doc {
+    text(&self.source[src.range.clone()])
+  }
+
+  fn pattern(&mut self, pattern: &Pattern) -> Doc {
+    let comments = self.comments_before(&pattern.src);
+    let doc = match &*pattern.data {
+      PatternType::Variable { name } => text(name.clone()),
+      PatternType::Variant { variant } => text(format!(".{variant}")),
+      PatternType::Literal { literal } => self.literal(literal, &pattern.src),
+      PatternType::Tuple { elements } => {
+        let elements = elements.iter().map(|e| self.pattern(e)).collect::<Vec<_>>();
+        concat([text("("), join(elements, text(", ")), text(")")])
+      }
+      PatternType::Nil => text("[]"),
+      PatternType::Cons { head, tail } => {
+        concat([self.pattern(head), text(" :: "), self.pattern(tail)])
+      }
+    };
+    concat([comments, doc])
+  }
+}
+
+/// `elements` between `open` and `close`, one per line if they don't fit.
+fn delimited(open: &str, elements: Doc, close: &str) -> Doc {
+  group(concat([
+    text(open),
+    nest(INDENT, concat([Doc::SoftLine, elements])),
+    Doc::SoftLine,
+    text(close),
+  ]))
+}
+
+/// A function name, operators going in parentheses.
+pub fn name(name: &str) -> String {
+  match name.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+    true => name.to_string(),
+    false => format!("({name})"),
+  }
+}
+
+/// `program` with every position cleared, to compare it by its structure.
+fn spanless(program: &Program) -> Program {
+  let mut program = program.clone();
+  program.imports.iter_mut().for_each(|i| clear(&mut i.src));
+  program.fixities.iter_mut().for_each(|f| clear(&mut f.src));
+  for declaration in &mut program.declarations {
+    match declaration {
+      TopLevel::Function(function) => {
+        clear(&mut function.src);
+        function
+          .parameters
+          .iter_mut()
+          .for_each(|p| clear(&mut p.src));
+        clear_expression(&mut function.body);
+      }
+      TopLevel::Enum(enum_) => {
+        clear(&mut enum_.src);
+        enum_.variants.iter_mut().for_each(|v| clear(&mut v.src));
+      }
+    }
+  }
+  program
+}
+
+fn clear(src: &mut Src) {
+  src.range = 0..0;
+}
+
+fn clear_expression(expression: &mut Expression) {
+  clear(&mut expression.src);
+  match &mut *expression.data {
+    ExpressionType::Unit
+    | ExpressionType::Hole { .. }
+    | ExpressionType::Variable { .. }
+    | ExpressionType::Literal { .. }
+    | ExpressionType::Variant { .. } => {}
+    ExpressionType::Fun { parameters, body } => {
+      parameters.iter_mut().for_each(clear_pattern);
+      clear_expression(body);
+    }
+    ExpressionType::Application { function, argument } => {
+      clear_expression(function);
+      clear_expression(argument);
+    }
+    ExpressionType::Interpolation { parts: elements }
+    | ExpressionType::Tuple { elements }
+    | ExpressionType::List { elements } => elements.iter_mut().for_each(clear_expression),
+    ExpressionType::Let { bind, value, next } => {
+      clear_pattern(bind);
+      clear_expression(value);
+      clear_expression(next);
+    }
+    ExpressionType::If {
+      condition,
+      then,
+      otherwise,
+    } => {
+      clear_expression(condition);
+      clear_expression(then);
+      clear_expression(otherwise);
+    }
+    ExpressionType::Match { scrutinee, arms } => {
+      clear_expression(scrutinee);
+      for arm in arms {
+        clear_pattern(&mut arm.left);
+        clear_expression(&mut arm.right);
+      }
+    }
+    ExpressionType::Infix { first, rest } => {
+      clear_expression(first);
+      for (operator, operand) in rest {
+        clear(&mut operator.src);
+        clear_expression(operand);
+      }
+    }
+    ExpressionType::Section {
+      operator,
+      left,
+      right,
+    } => {
+      clear(&mut operator.src);
+      left
+        .iter_mut()
+        .chain(right)
+        .for_each(|e| clear_expression(e));
+    }
+    ExpressionType::BinaryOp { lhs, rhs, .. } => {
+      clear_expression(lhs);
+      clear_expression(rhs);
+    }
+    ExpressionType::UnaryOp { operand, .. } => clear_expression(operand),
+    ExpressionType::Projection { tuple, .. } => clear_expression(tuple),
+  }
+}
+
+fn clear_pattern(pattern: &mut Pattern) {
+  clear(&mut pattern.src);
+  match &mut *pattern.data {
+    PatternType::Tuple { elements } => elements.iter_mut().for_each(clear_pattern),
+    PatternType::Cons { head, tail } => {
+      clear_pattern(head);
+      clear_pattern(tail);
+    }
+    PatternType::Variable { .. }
+    | PatternType::Variant { .. }
+    | PatternType::Literal { .. }
+    | PatternType::Nil => {}
+  }
+}
+
+impl Diagnostic for FormatError {
+  fn message(&self) -> String {
+    "Formatting would change the program, so it was left as is.".to_string()
+  }
+
+  fn severity(&self) -> Severity {
+    Severity::Error
+  }
+
+  fn code(&self) -> &'static str {
+    "unstable-format"
+  }
+
+  fn extra(&self) -> Vec<String> {
+    vec!["This is a bug in the formatter.".to_string()]
+  }
+
+  fn src(&self) -> Option<Src> {
+    let FormatError::Unstable(src) = self;
+    Some(src.clone())
+  }
+}
@@ -0,0 +1,117 @@
+/// A layout, where groups are printed on one line when they fit and have
+/// their line breaks taken otherwise (Wadler's "prettier printer").
+#[derive(Clone, Debug)]
+pub enum Doc {
+  Text(String),
+  /// A space, or a line break when its group doesn't fit.
+  Line,
+  /// Nothing, or a line break when its group doesn't fit.
+  SoftLine,
+  /// Always a line break, so its groups never fit on one line.
+  HardLine,
+  Concat(Vec<Doc>),
+  /// Indents the lines broken inside by some columns.
+  Nest(usize, Box<Doc>),
+  Group(Box<Doc>),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+  Flat,
+  Break,
+}
+
+pub fn text(s: impl Into<String>) -> Doc {
+  Doc::Text(s.into())
+}
+
+pub fn concat(docs: impl IntoIterator<Item = Doc>) -> Doc {
+  Doc::Concat(docs.into_iter().collect())
+}
+
+pub fn nest(indent: usize, doc: Doc) -> Doc {
+  Doc::Nest(indent, Box::new(doc))
+}
+
+pub fn group(doc: Doc) -> Doc {
+  Doc::Group(Box::new(doc))
+}
+
+/// `docs` separated by `separator`.
+pub fn join(docs: impl IntoIterator<Item = Doc>, separator: Doc) -> Doc {
+  let mut joined = Vec::new();
+  for doc in docs {
+    if !joined.is_empty() {
+      joined.push(separator.clone());
+    }
+    joined.push(doc);
+  }
+  Doc::Concat(joined)
+}
+
+/// Lays `doc` out in lines of at most `width` columns where possible.
+pub fn render(doc: &Doc, width: usize) -> String {
+  let mut out = String::new();
+  let mut column = 0;
+  let mut stack = vec![(0, Mode::Break, doc)];
+
+  while let Some((indent, mode, doc)) = stack.pop() {
+    match doc {
+      Doc::Text(s) => {
+        out.push_str(s);
+        column = match s.rfind('\n') {
+          Some(i) => s[i + 1..].chars().count(),
+          None => column + s.chars().count(),
+        };
+      }
+      Doc::Line if mode == Mode::Flat => {
+        out.push(' ');
+        column += 1;
+      }
+      Doc::SoftLine if mode == Mode::Flat => {}
+      Doc::Line | Doc::SoftLine | Doc::HardLine => {
+        out.truncate(out.trim_end_matches(' ').len());
+        out.push('\n');
+        out.push_str(&" ".repeat(indent));
+        column = indent;
+      }
+      Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc))),
+      Doc::Nest(more, doc) => stack.push((indent + more, mode, doc)),
+      Doc::Group(doc) => {
+        let fits = mode == Mode::Flat || fits(width as isize - column as isize, doc, &stack);
+        let mode = if fits { Mode::Flat } else { Mode::Break };
+        stack.push((indent, mode, doc));
+      }
+    }
+  }
+  out
+}
+
+/// Whether `doc` fits in `remaining` columns on one line, along with what
+/// follows it up to the next line break.
+fn fits(mut remaining: isize, doc: &Doc, rest: &[(usize, Mode, &Doc)]) -> bool {
+  let mut stack = vec![(Mode::Flat, doc)];
+  let mut rest = rest.iter().rev();
+  loop {
+    let (mode, doc) = match stack.pop() {
+      Some(next) => next,
+      None => match rest.next() {
+        Some((_, mode, doc)) => (*mode, *doc),
+        None => return true,
+      },
+    };
+    match doc {
+      Doc::Text(s) if s.contains('\n') => return false,
+      Doc::Text(s) => remaining -= s.chars().count() as isize,
+      Doc::Line if mode == Mode::Flat => remaining -= 1,
+      Doc::SoftLine if mode == Mode::Flat => {}
+      Doc::HardLine if mode == Mode::Flat => return false,
+      Doc::Line | Doc::SoftLine | Doc::HardLine => return true,
+      Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (mode, doc))),
+      Doc::Nest(_, doc) | Doc::Group(doc) => stack.push((mode, doc)),
+    }
+    if remaining < 0 {
+      return false;
+    }
+  }
+}
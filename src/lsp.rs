@@ -0,0 +1,503 @@
+use std::{
+  collections::HashMap,
+  io,
+  path::{Path, PathBuf},
+};
+
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+  notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+    PublishDiagnostics,
+  },
+  request::{Completion, DocumentSymbolRequest, GotoDefinition, HoverRequest, Request as _},
+  CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse,
+  DiagnosticSeverity, DocumentSymbol, DocumentSymbolParams, DocumentSymbolResponse,
+  GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents, HoverParams,
+  HoverProviderCapability, Location, MarkupContent, MarkupKind, NumberOrString, OneOf, Position,
+  PublishDiagnosticsParams, Range, ServerCapabilities, SymbolKind, TextDocumentPositionParams,
+  TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+};
+
+use crate::{
+  ast::Src,
+  checker::{index::Index, types::TypeKind, Env},
+  module::{Definition, Loader},
+  report::{sink::CollectSink, Diagnostic, Reporter, Severity},
+  source::{FileId, Source, SourceMap},
+};
+
+/// A language server for the documents an editor has open, over stdio.
+///
+/// Every change checks the whole document again, publishing its
+/// diagnostics. Positions are in UTF-16 code units, as LSP has them by
+/// default.
+struct Server {
+  connection: Connection,
+  /// The directories imports are searched in besides the document's.
+  module_path: Vec<PathBuf>,
+  prelude: bool,
+  documents: HashMap<Url, Document>,
+}
+
+/// An open document, as it was last checked.
+struct Document {
+  sources: SourceMap,
+  file: FileId,
+  /// The scope at the end of the document, with the index of its places.
+  env: Env,
+  defined: Vec<Definition>,
+}
+
+/// Runs a server on stdio until the editor shuts it down.
+pub fn run(module_path: Vec<PathBuf>, prelude: bool) -> io::Result<()> {
+  let (connection, io_threads) = Connection::stdio();
+  let capabilities = ServerCapabilities {
+    text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+    hover_provider: Some(HoverProviderCapability::Simple(true)),
+    definition_provider: Some(OneOf::Left(true)),
+    completion_provider: Some(CompletionOptions {
+      trigger_characters: Some(vec![".".to_string()]),
+      ..CompletionOptions::default()
+    }),
+    document_symbol_provider: Some(OneOf::Left(true)),
+    ..ServerCapabilities::default()
+  };
+  let capabilities = serde_json::to_value(capabilities)?;
+  connection
+    .initialize(capabilities)
+    .map_err(|e| io::Error::other(e.to_string()))?;
+
+  let mut server = Server {
+    connection,
+    module_path,
+    prelude,
+    documents: HashMap::new(),
+  };
+  server.main_loop()?;
+  drop(server);
+  io_threads.join()
+}
+
+impl Server {
+  fn main_loop(&mut self) -> io::Result<()> {
+    let receiver = self.connection.receiver.clone();
+    for message in receiver {
+      match message {
+        Message::Request(request) => {
+          let shutdown = self
+            .connection
+            .handle_shutdown(&request)
+            .map_err(|e| io::Error::other(e.to_string()))?;
+          if shutdown {
+            return Ok(());
+          }
+          let response = self.request(request);
+          self.send(Message::Response(response))?;
+        }
+        Message::Notification(notification) => self.notification(notification)?,
+        Message::Response(_) => {}
+      }
+    }
+    Ok(())
+  }
+
+  fn send(&self, message: Message) -> io::Result<()> {
+    self
+      .connection
+      .sender
+      .send(message)
+      .map_err(|e| io::Error::other(e.to_string()))
+  }
+
+  fn request(&self, request: Request) -> Response {
+    match request.method.as_str() {
+      HoverRequest::METHOD => respond::<HoverRequest>(request, |params| self.hover(params)),
+      GotoDefinition::METHOD => {
+        respond::<GotoDefinition>(request, |params| self.definition(params))
+      }
+      Completion::METHOD => respond::<Completion>(request, |params| self.completion(params)),
+      DocumentSymbolRequest::METHOD => {
+        respond::<DocumentSymbolRequest>(request, |params| self.symbols(params))
+      }
+      method => Response::new_err(
+        request.id,
+        ErrorCode::MethodNotFound as i32,
+        format!("Unknown request `{method}`."),
+      ),
+    }
+  }
+
+  fn notification(&mut self, notification: Notification) -> io::Result<()> {
+    match notification.method.as_str() {
+      DidOpenTextDocument::METHOD => {
+        let Some(params) = parameters::<DidOpenTextDocument>(notification) else {
+          return Ok(());
+        };
+        self.change(params.text_document.uri, params.text_document.text)
+      }
+      DidChangeTextDocument::METHOD => {
+        let Some(mut params) = parameters::<DidChangeTextDocument>(notification) else {
+          return Ok(());
+        };
+        // Changes are whole documents, as the server asks for.
+        match params.content_changes.pop() {
+          Some(change) => self.change(params.text_document.uri, change.text),
+          None => Ok(()),
+        }
+      }
+      DidCloseTextDocument::METHOD => {
+        let Some(params) = parameters::<DidCloseTextDocument>(notification) else {
+          return Ok(());
+        };
+        self.documents.remove(&params.text_document.uri);
+        self.publish(params.text_document.uri, vec![])
+      }
+      _ => Ok(()),
+    }
+  }
+
+  /// Checks the new `text` of the document at `uri` and publishes its
+  /// diagnostics. A document that doesn't parse keeps answering from the
+  /// last version that did, as long as there is one.
+  fn change(&mut self, uri: Url, text: String) -> io::Result<()> {
+    let path = uri
+      .to_file_path()
+      .unwrap_or_else(|_| PathBuf::from(uri.path()));
+    let (reporter, recv) = Reporter::new();
+    let mut search_path = vec![path.parent().unwrap_or(Path::new(".")).to_path_buf()];
+    search_path.extend(self.module_path.iter().cloned());
+
+    let mut sources = SourceMap::new();
+    let mut loader = Loader::new(search_path, reporter.clone());
+    if self.prelude {
+      loader.load_prelude(&mut sources);
+    }
+    let file = sources.add(path.display().to_string(), text);
+    let (scope, _) = loader.load_root_file(file, &path, None, &mut sources);
+
+    let mut sink = CollectSink::default();
+    Reporter::drain(&recv, &sources, &mut sink);
+    let diagnostics = sink
+      .diagnostics
+      .iter()
+      .map(|diagnostic| lsp_diagnostic(diagnostic, file, &sources))
+      .collect();
+
+    if !loader.parse_failed || !self.documents.contains_key(&uri) {
+      let document = Document {
+        sources,
+        file,
+        env: scope.env,
+        defined: scope.defined,
+      };
+      self.documents.insert(uri.clone(), document);
+    }
+    self.publish(uri, diagnostics)
+  }
+
+  fn publish(&self, uri: Url, diagnostics: Vec<lsp_types::Diagnostic>) -> io::Result<()> {
+    let params = PublishDiagnosticsParams {
+      uri,
+      diagnostics,
+      version: None,
+    };
+    let notification = Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+    self.send(Message::Notification(notification))
+  }
+
+  /// The document `params` point into, and the offset they point at.
+  fn locate(&self, params: &TextDocumentPositionParams) -> Option<(&Document, usize)> {
+    let document = self.documents.get(&params.text_document.uri)?;
+    let offset = offset(document.source(), params.position);
+    Some((document, offset))
+  }
+
+  /// The type of the innermost expression or binder under the cursor.
+  fn hover(&self, params: HoverParams) -> Option<Hover> {
+    let (document, offset) = self.locate(&params.text_document_position_params)?;
+    let index = document.env.index.borrow();
+    let (src, t) = Index::innermost(&index.types, offset)?;
+    Some(Hover {
+      contents: HoverContents::Markup(MarkupContent {
+        kind: MarkupKind::Markdown,
+        value: format!("```oktu\n{t}\n```"),
+      }),
+      range: Some(document.range(src)),
+    })
+  }
+
+  /// Where the name under the cursor is defined, in this document, an
+  /// imported module or the prelude.
+  fn definition(&self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+    let position = params.text_document_position_params;
+    let (document, offset) = self.locate(&position)?;
+    let index = document.env.index.borrow();
+    let (_, definition) = Index::innermost(&index.references, offset)?;
+    let location = if definition.file == document.file {
+      Location {
+        uri: position.text_document.uri,
+        range: document.range(definition),
+      }
+    } else {
+      let source = document.sources.get(definition.file);
+      Location {
+        uri: file_uri(source)?,
+        range: range(source, definition),
+      }
+    };
+    Some(GotoDefinitionResponse::Scalar(location))
+  }
+
+  /// The names in scope at the cursor, or the variants after a `.`.
+  fn completion(&self, params: CompletionParams) -> Option<CompletionResponse> {
+    let (document, offset) = self.locate(&params.text_document_position)?;
+    let env = &document.env;
+    let word = document.source().text[..offset]
+      .trim_end_matches(|c: char| c.is_alphanumeric() || c == '_' || c == '\'');
+
+    let mut items = Vec::new();
+    if word.ends_with('.') {
+      for (variant, name) in env.variant_to_enum.iter() {
+        items.push(CompletionItem {
+          label: variant.clone(),
+          kind: Some(CompletionItemKind::ENUM_MEMBER),
+          detail: Some(name.clone()),
+          ..CompletionItem::default()
+        });
+      }
+      return Some(CompletionResponse::Array(items));
+    }
+
+    // Inner scopes come last, shadowing the outer ones.
+    let index = env.index.borrow();
+    let mut scopes = index
+      .scopes
+      .iter()
+      .filter(|(src, _)| src.range.start <= offset && offset <= src.range.end)
+      .collect::<Vec<_>>();
+    scopes.sort_by_key(|(src, _)| std::cmp::Reverse(src.range.len()));
+    let mut names = HashMap::new();
+    for (name, scheme) in env.let_decls.iter() {
+      names.insert(name.clone(), scheme.t.clone());
+    }
+    for (_, variables) in scopes {
+      for (name, t) in variables {
+        names.insert(name.clone(), t.clone());
+      }
+    }
+
+    for (name, t) in names {
+      if !name.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+        continue;
+      }
+      let kind = match &*t.clone().resolve() {
+        TypeKind::Arrow { .. } => CompletionItemKind::FUNCTION,
+        _ => CompletionItemKind::VARIABLE,
+      };
+      items.push(CompletionItem {
+        label: name,
+        kind: Some(kind),
+        detail: Some(t.to_string()),
+        ..CompletionItem::default()
+      });
+    }
+    Some(CompletionResponse::Array(items))
+  }
+
+  fn symbols(&self, params: DocumentSymbolParams) -> Option<DocumentSymbolResponse> {
+    let document = self.documents.get(&params.text_document.uri)?;
+    let symbols = document
+      .defined
+      .iter()
+      .map(|definition| match &definition.variants {
+        None => {
+          let t = document
+            .env
+            .let_decls
+            .get(&definition.name)
+            .map(|s| s.t.clone());
+          let kind = match t.as_ref().map(|t| t.clone().resolve()).as_deref() {
+            Some(TypeKind::Arrow { .. }) => SymbolKind::FUNCTION,
+            _ => SymbolKind::VARIABLE,
+          };
+          symbol(
+            definition.name.clone(),
+            t.map(|t| t.to_string()),
+            kind,
+            document.range(&definition.src),
+            None,
+          )
+        }
+        Some(variants) => {
+          let variants = variants
+            .iter()
+            .map(|variant| {
+              let range = document.range(&variant.src);
+              symbol(
+                *variant.data.clone(),
+                None,
+                SymbolKind::ENUM_MEMBER,
+                range,
+                None,
+              )
+            })
+            .collect();
+          let range = document.range(&definition.src);
+          symbol(
+            definition.name.clone(),
+            None,
+            SymbolKind::ENUM,
+            range,
+            Some(variants),
+          )
+        }
+      })
+      .collect();
+    Some(DocumentSymbolResponse::Nested(symbols))
+  }
+}
+
+impl Document {
+  fn source(&self) -> &Source {
+    self.sources.get(self.file)
+  }
+
+  fn range(&self, src: &Src) -> Range {
+    range(self.source(), src)
+  }
+}
+
+fn respond<R: lsp_types::request::Request>(
+  request: Request,
+  handler: impl FnOnce(R::Params) -> R::Result,
+) -> Response {
+  match serde_json::from_value(request.params) {
+    Ok(params) => Response::new_ok(request.id, handler(params)),
+    Err(error) => Response::new_err(
+      request.id,
+      ErrorCode::InvalidParams as i32,
+      error.to_string(),
+    ),
+  }
+}
+
+/// The parameters of `notification`, or `None` once it is logged that they
+/// are malformed, as there is no one to answer with an error.
+fn parameters<N: lsp_types::notification::Notification>(
+  notification: Notification,
+) -> Option<N::Params> {
+  match serde_json::from_value(notification.params) {
+    Ok(params) => Some(params),
+    Err(error) => {
+      eprintln!("Ignoring a malformed `{}` notification: {error}", N::METHOD);
+      None
+    }
+  }
+}
+
+/// The URI of the file `source` was read from. Sources that weren't read
+/// from a file, like the prelude, are written to one in the temporary
+/// directory for the editor to open.
+fn file_uri(source: &Source) -> Option<Url> {
+  if let Ok(path) = std::fs::canonicalize(&source.name) {
+    return Url::from_file_path(path).ok();
+  }
+  let name = source.name.trim_matches(|c| c == '<' || c == '>');
+  let path = std::env::temp_dir()
+    .join("oktu")
+    .join(format!("{name}.oktu"));
+  if std::fs::read_to_string(&path).ok().as_deref() != Some(&source.text) {
+    std::fs::create_dir_all(path.parent()?).ok()?;
+    std::fs::write(&path, &source.text).ok()?;
+  }
+  Url::from_file_path(path).ok()
+}
+
+fn lsp_diagnostic(
+  diagnostic: &dyn Diagnostic,
+  file: FileId,
+  sources: &SourceMap,
+) -> lsp_types::Diagnostic {
+  let mut message = diagnostic.message();
+  for extra in diagnostic.extra() {
+    message.push('\n');
+    message.push_str(&extra);
+  }
+  // Diagnostics of imported modules go at the top, saying where they are.
+  let range = match diagnostic.src() {
+    Some(src) if src.file == file => range(sources.get(file), &src),
+    Some(src) => {
+      let source = sources.get(src.file);
+      let (line, column) = source.line_col(src.range.start);
+      message = format!("{}:{line}:{column}: {message}", source.name);
+      Range::default()
+    }
+    None => Range::default(),
+  };
+  let severity = match diagnostic.severity() {
+    Severity::Error => DiagnosticSeverity::ERROR,
+    Severity::Warning => DiagnosticSeverity::WARNING,
+    Severity::Info => DiagnosticSeverity::INFORMATION,
+  };
+  lsp_types::Diagnostic {
+    range,
+    severity: Some(severity),
+    code: Some(NumberOrString::String(diagnostic.code().to_string())),
+    source: Some("oktu".to_string()),
+    message,
+    ..lsp_types::Diagnostic::default()
+  }
+}
+
+#[allow(deprecated)]
+fn symbol(
+  name: String,
+  detail: Option<String>,
+  kind: SymbolKind,
+  range: Range,
+  children: Option<Vec<DocumentSymbol>>,
+) -> DocumentSymbol {
+  DocumentSymbol {
+    name,
+    detail,
+    kind,
+    tags: None,
+    deprecated: None,
+    range,
+    selection_range: range,
+    children,
+  }
+}
+
+/// The LSP range of `src` in `source`.
+fn range(source: &Source, src: &Src) -> Range {
+  Range::new(
+    position(source, src.range.start),
+    position(source, src.range.end),
+  )
+}
+
+/// The LSP position of the byte `offset`.
+fn position(source: &Source, offset: usize) -> Position {
+  let offset = offset.min(source.text.len());
+  let line = source.line_index(offset);
+  let column = source.text[source.line_start(line)..offset]
+    .encode_utf16()
+    .count();
+  Position::new(line as u32, column as u32)
+}
+
+/// The byte offset of an LSP `position`, clamped to its line.
+fn offset(source: &Source, position: Position) -> usize {
+  let line = (position.line as usize).min(source.line_count() - 1);
+  let start = source.line_start(line);
+  let mut units = 0;
+  for (i, c) in source.line(line).char_indices() {
+    if units >= position.character as usize {
+      return start + i;
+    }
+    units += c.len_utf16();
+  }
+  start + source.line(line).len()
+}
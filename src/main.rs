@@ -1,77 +1,269 @@
-use std::{io::Read, path::PathBuf};
+use std::{
+  io::IsTerminal,
+  path::{Path, PathBuf},
+  process::ExitCode,
+};
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use lalrpop_util::lalrpop_mod;
-use report::Reporter;
+use report::{
+  sink::{JsonSink, Sink, TerminalSink},
+  Reporter,
+};
 
-use crate::checker::{infer::Infer, Env};
+use crate::{elab::to_bend::BackendError, module::Loader, source::SourceMap};
 
 pub mod ast;
+pub mod builtins;
 pub mod checker;
 pub mod elab;
+pub mod format;
+pub mod lsp;
+pub mod module;
+pub mod operators;
+pub mod parse;
+pub mod repl;
 pub mod report;
-lalrpop_mod!(pub parser);
+pub mod source;
+lalrpop_mod!(#[allow(clippy::all)] pub parser);
 
 #[derive(Clone, Parser)]
 struct Cli {
   #[command(subcommand)]
   pub command: Cmd,
+  /// When to use colors in diagnostics.
+  #[arg(long, value_enum, default_value_t = ColorChoice::Auto, global = true)]
+  pub color: ColorChoice,
+  /// How diagnostics are printed.
+  #[arg(long, value_enum, default_value_t = MessageFormat::Human, global = true)]
+  pub message_format: MessageFormat,
+  /// Additional directories where imported modules are searched for.
+  #[arg(long = "module-path", short = 'I', global = true)]
+  pub module_path: Vec<PathBuf>,
+  /// Don't load the prelude.
+  #[arg(long, global = true)]
+  pub no_prelude: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum MessageFormat {
+  /// Annotated source snippets.
+  Human,
+  /// One JSON object per line.
+  Json,
+  /// One `file:line:col: severity: message` line per diagnostic.
+  Short,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ColorChoice {
+  Auto,
+  Always,
+  Never,
+}
+
+impl ColorChoice {
+  fn enabled(self) -> bool {
+    match self {
+      ColorChoice::Auto => {
+        std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+      }
+      ColorChoice::Always => true,
+      ColorChoice::Never => false,
+    }
+  }
 }
 
 #[derive(Clone, Subcommand)]
 pub enum Cmd {
   /// Type checks the program.
-  Check { path: PathBuf },
+  Check {
+    path: PathBuf,
+    /// Print the program's definitions with the type of every expression.
+    #[arg(long)]
+    dump_typed: bool,
+  },
   /// Compiles the program to Bend.
   Compile { path: PathBuf },
+  /// Runs the program's `main` with an interpreter and prints its value.
+  Eval { path: PathBuf },
+  /// Formats programs in place.
+  Fmt {
+    paths: Vec<PathBuf>,
+    /// List the programs that aren't formatted instead of formatting them.
+    #[arg(long)]
+    check: bool,
+  },
+  /// Starts an interactive session.
+  Repl,
+  /// Starts a language server on stdio.
+  Lsp,
 }
 
-fn main() {
-  if let Err(e) = run() {
-    eprintln!("Error: {e}");
+/// Exit code used when the program has syntax errors.
+const PARSE_FAILURE: u8 = 3;
+/// Exit code used when the program has type errors.
+const TYPE_FAILURE: u8 = 4;
+/// Exit code used when the program could not be compiled to Bend.
+const BACKEND_FAILURE: u8 = 5;
+/// Exit code used when `fmt --check` finds programs that aren't formatted.
+const FORMAT_FAILURE: u8 = 6;
+/// Exit code used when evaluating the program fails.
+const EVAL_FAILURE: u8 = 7;
+
+/// The definition `eval` runs.
+const ENTRYPOINT: &str = "main";
+/// Evaluation recurses as deep as the program does, so everything runs on a
+/// thread with a larger stack than the main thread's.
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Action {
+  Check,
+  DumpTyped,
+  Compile,
+  Eval,
+}
+
+fn main() -> ExitCode {
+  let thread = std::thread::Builder::new()
+    .stack_size(STACK_SIZE)
+    .spawn(run);
+  match thread.map(|thread| thread.join()) {
+    Ok(Ok(Ok(code))) => code,
+    Ok(Ok(Err(e))) | Err(e) => {
+      eprintln!("Error: {e}");
+      ExitCode::FAILURE
+    }
+    Ok(Err(_)) => ExitCode::FAILURE,
   }
 }
 
-fn run() -> std::io::Result<()> {
+fn run() -> std::io::Result<ExitCode> {
   let cli = Cli::parse();
   let (reporter, recv) = Reporter::new();
+  let color = cli.color.enabled();
+  let mut sink: Box<dyn Sink> = match cli.message_format {
+    MessageFormat::Human => Box::new(TerminalSink {
+      color,
+      short: false,
+    }),
+    MessageFormat::Json => Box::new(JsonSink::new(std::io::stderr())),
+    MessageFormat::Short => Box::new(TerminalSink { color, short: true }),
+  };
 
-  match cli.command {
-    Cmd::Check { path } => {
-      let mut file = std::fs::File::open(&path)?;
-      let input = read_file(&mut file)?;
-      match parser::ProgramParser::new().parse(&input) {
-        Ok(mut program) => {
-          program.set_file_name(path.to_str().map(Box::from));
-          let env = Env::new(reporter);
-          _ = program.infer(env);
-          Reporter::to_stdout(recv, file);
-        }
-        Err(e) => eprintln!("{e}"),
-      };
+  let mut module_path = cli.module_path;
+  if let Some(oktu_path) = std::env::var_os("OKTU_PATH") {
+    module_path.extend(std::env::split_paths(&oktu_path));
+  }
+
+  let (path, action) = match cli.command {
+    Cmd::Check { path, dump_typed } => match dump_typed {
+      true => (path, Action::DumpTyped),
+      false => (path, Action::Check),
+    },
+    Cmd::Compile { path } => (path, Action::Compile),
+    Cmd::Eval { path } => (path, Action::Eval),
+    Cmd::Fmt { paths, check } => {
+      let mut sources = SourceMap::new();
+      let unformatted = format_files(&paths, check, &reporter, &mut sources)?;
+      Reporter::drain(&recv, &sources, sink.as_mut());
+      return Ok(match unformatted {
+        _ if reporter.has_errors() => ExitCode::from(PARSE_FAILURE),
+        true if check => ExitCode::from(FORMAT_FAILURE),
+        _ => ExitCode::SUCCESS,
+      });
     }
-    Cmd::Compile { path } => {
-      let mut file = std::fs::File::open(&path)?;
-      let input = read_file(&mut file)?;
-      match parser::ProgramParser::new().parse(&input) {
-        Ok(mut program) => {
-          program.set_file_name(path.to_str().map(Box::from));
-          let env = Env::new(reporter);
-          let (program, _) = program.infer(env);
-          Reporter::to_stdout(recv, file);
-          let output = program.to_bend().map_err(std::io::Error::other)?;
-          println!("{}", output.display_pretty());
-        }
-        Err(e) => eprintln!("{e}"),
-      };
+    Cmd::Repl => {
+      let mut search_path = vec![PathBuf::from(".")];
+      search_path.extend(module_path);
+      repl::Repl::new(search_path, !cli.no_prelude, sink).run(std::io::stdin().lock())?;
+      return Ok(ExitCode::SUCCESS);
+    }
+    Cmd::Lsp => {
+      lsp::run(module_path, !cli.no_prelude)?;
+      return Ok(ExitCode::SUCCESS);
+    }
+  };
+
+  let mut search_path = vec![path.parent().unwrap_or(Path::new(".")).to_path_buf()];
+  search_path.extend(module_path);
+
+  let mut sources = SourceMap::new();
+  let mut loader = Loader::new(search_path, reporter.clone());
+  if !cli.no_prelude {
+    loader.load_prelude(&mut sources);
+  }
+  let text = std::fs::read_to_string(&path)?;
+  let file = sources.add(path.display().to_string(), text);
+  let (_, mut program) = loader.load_root_file(file, &path, None, &mut sources);
+  let parse_failed = loader.parse_failed;
+  let type_failed = reporter.has_errors();
+
+  let mut output = None;
+  if action == Action::Compile && !type_failed {
+    match std::mem::take(&mut program).to_bend() {
+      Ok(book) => output = Some(book),
+      Err(message) => reporter.report(BackendError(message)),
+    }
+  }
+
+  Reporter::drain(&recv, &sources, sink.as_mut());
+
+  if parse_failed {
+    return Ok(ExitCode::from(PARSE_FAILURE));
+  }
+  if action == Action::DumpTyped {
+    print!("{}", program.dump_typed(file, &sources));
+  }
+  if type_failed {
+    return Ok(ExitCode::from(TYPE_FAILURE));
+  }
+  match output {
+    Some(book) => println!("{}", book.display_pretty()),
+    None if action == Action::Compile => return Ok(ExitCode::from(BACKEND_FAILURE)),
+    None => {}
+  }
+
+  if action == Action::Eval {
+    let result = program.eval(ENTRYPOINT, std::io::stdout().lock());
+    match result {
+      Ok(elab::eval::Value::Unit) => {}
+      Ok(value) => println!("{value}"),
+      Err(error) => {
+        reporter.report(error);
+        Reporter::drain(&recv, &sources, sink.as_mut());
+        return Ok(ExitCode::from(EVAL_FAILURE));
+      }
     }
   }
 
-  Ok(())
+  Ok(ExitCode::SUCCESS)
 }
 
-fn read_file(file: &mut std::fs::File) -> Result<String, std::io::Error> {
-  let mut buf = String::new();
-  file.read_to_string(&mut buf)?;
-  Ok(buf)
+/// Formats every file in `paths` in place, or with `check` prints those that
+/// aren't formatted. Returns whether any of them wasn't.
+fn format_files(
+  paths: &[PathBuf],
+  check: bool,
+  reporter: &Reporter,
+  sources: &mut SourceMap,
+) -> std::io::Result<bool> {
+  let mut unformatted = false;
+  for path in paths {
+    let text = std::fs::read_to_string(path)?;
+    let file = sources.add(path.display().to_string(), text.clone());
+    let Some(formatted) = format::format_source(file, &text, reporter) else {
+      continue;
+    };
+    if formatted == text {
+      continue;
+    }
+    unformatted = true;
+    match check {
+      true => println!("{}", path.display()),
+      false => std::fs::write(path, formatted)?,
+    }
+  }
+  Ok(unformatted)
 }
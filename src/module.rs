@@ -0,0 +1,457 @@
+use std::{
+  collections::{HashMap, HashSet},
+  path::{Path, PathBuf},
+};
+
+use crate::{
+  ast::{self, Fixity, Import, Src, TopLevel},
+  checker::{infer::Infer, types::Scheme, Env},
+  elab,
+  operators::{self, Fixities},
+  parse,
+  report::{Diagnostic, Reporter, Severity},
+  source::{FileId, SourceMap},
+};
+
+/// Loads, checks and links a program together with every module it imports.
+///
+/// Each module is checked on its own, with only the public definitions of
+/// its imports in scope. Definitions of imported modules are renamed to
+/// `module.name` so that every module can be linked into one program.
+pub struct Loader {
+  search_path: Vec<PathBuf>,
+  reporter: Reporter,
+  modules: HashMap<String, Exports>,
+  /// Modules currently being loaded, innermost last.
+  loading: Vec<String>,
+  /// Declarations of every checked module, in dependency order.
+  declarations: Vec<elab::TopLevel>,
+  /// Definitions brought into scope, unqualified, in every module.
+  prelude: Option<Exports>,
+  pub parse_failed: bool,
+}
+
+const PRELUDE_NAME: &str = "prelude";
+
+const PRELUDE: &str = include_str!("prelude.oktu");
+
+/// The definitions a module makes available to its importers.
+#[derive(Clone, Default)]
+pub struct Exports {
+  /// Public functions with their link names and types.
+  pub functions: HashMap<String, (String, Scheme)>,
+  /// Public enums with their link names and variants.
+  pub enums: HashMap<String, (String, Vec<String>)>,
+  /// Declared fixities of the public operators.
+  pub fixities: HashMap<String, Fixity>,
+  pub private: HashSet<String>,
+  /// Where the public functions and variants (as `.Variant`) are defined.
+  pub defined_at: HashMap<String, Src>,
+}
+
+/// What is in scope at some point of a module: the checker's environment
+/// and the operator fixities.
+#[derive(Clone)]
+pub struct Scope {
+  pub env: Env,
+  pub fixities: Fixities,
+  /// The module's own definitions, in order.
+  pub defined: Vec<Definition>,
+}
+
+/// A top level definition of a module.
+#[derive(Clone)]
+pub struct Definition {
+  pub name: String,
+  pub public: bool,
+  /// The variants, if it is an enum.
+  pub variants: Option<Vec<ast::Binder>>,
+  pub src: Src,
+}
+
+pub enum ModuleError {
+  NotFound(String, Vec<PathBuf>, Src),
+  Unreadable(String, String, Src),
+  Cycle(Vec<String>, Src),
+  Private(String, String, Src),
+  UnknownItem(String, String, Src),
+}
+
+impl Loader {
+  pub fn new(search_path: Vec<PathBuf>, reporter: Reporter) -> Self {
+    Self {
+      search_path,
+      reporter,
+      modules: HashMap::new(),
+      loading: Vec::new(),
+      declarations: Vec::new(),
+      prelude: None,
+      parse_failed: false,
+    }
+  }
+
+  /// Checks the prelude, making its public definitions available to every
+  /// module loaded afterwards.
+  pub fn load_prelude(&mut self, sources: &mut SourceMap) {
+    let file = sources.add(format!("<{PRELUDE_NAME}>"), PRELUDE.to_string());
+    let program = self.parse(file, sources);
+    let (exports, _, program) = self.check(Some(PRELUDE_NAME.to_string()), program, sources);
+    self.declarations.extend(program.declarations);
+    self.prelude = Some(exports);
+  }
+
+  /// Checks the program at `path` and every module it depends on, returning
+  /// them linked together.
+  pub fn load_root(
+    &mut self,
+    path: &Path,
+    sources: &mut SourceMap,
+  ) -> std::io::Result<elab::Program> {
+    let (_, program) = self.load_root_scope(path, None, sources)?;
+    Ok(program)
+  }
+
+  /// Checks the program at `path` like [`Loader::load_root`], as `module`,
+  /// also returning the scope at its end.
+  pub fn load_root_scope(
+    &mut self,
+    path: &Path,
+    module: Option<String>,
+    sources: &mut SourceMap,
+  ) -> std::io::Result<(Scope, elab::Program)> {
+    let input = std::fs::read_to_string(path)?;
+    let file = sources.add(path.display().to_string(), input);
+    Ok(self.load_root_file(file, path, module, sources))
+  }
+
+  /// Checks the program at `path` like [`Loader::load_root_scope`], with
+  /// the text it has in `file` instead of the one on disk.
+  pub fn load_root_file(
+    &mut self,
+    file: FileId,
+    path: &Path,
+    module: Option<String>,
+    sources: &mut SourceMap,
+  ) -> (Scope, elab::Program) {
+    let mut program = self.parse(file, sources);
+    program.set_file_name(path.to_str().map(Box::from));
+
+    if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+      self.loading.push(name.to_string());
+    }
+    let (_, scope, root) = self.check(module, program, sources);
+    self.loading.clear();
+
+    self.declarations.extend(root.declarations);
+    let program = elab::Program {
+      file_name: root.file_name,
+      declarations: self.take_declarations(),
+    };
+    (scope, program)
+  }
+
+  /// The declarations of every module checked since the last call, in
+  /// dependency order.
+  pub fn take_declarations(&mut self) -> Vec<elab::TopLevel> {
+    std::mem::take(&mut self.declarations)
+  }
+
+  /// The scope a module starts in, with the builtins and the prelude.
+  pub fn empty_scope(&self, module: Option<String>) -> Scope {
+    let mut env = Env::new(self.reporter.clone());
+    env.module = module;
+    let mut fixities = Fixities::new();
+
+    if let Some(prelude) = &self.prelude {
+      for (name, (link_name, scheme)) in prelude.functions.iter() {
+        env.let_decls.insert(name.clone(), scheme.clone());
+        env.link_names.insert(name.clone(), link_name.clone());
+        if let Some(src) = prelude.defined_at.get(name) {
+          env.defined_at.insert(name.clone(), src.clone());
+        }
+      }
+      for (name, fixity) in prelude.fixities.iter() {
+        fixities.insert(name.clone(), *fixity);
+      }
+      for (name, (link_name, variants)) in prelude.enums.iter() {
+        env.link_names.insert(name.clone(), link_name.clone());
+        import_enum(&mut env, &prelude.defined_at, link_name, variants);
+      }
+    }
+
+    Scope {
+      env,
+      fixities,
+      defined: Vec::new(),
+    }
+  }
+
+  /// Checks `program` in `scope`, adding its imports and definitions to it.
+  pub fn extend(
+    &mut self,
+    scope: &mut Scope,
+    program: ast::Program,
+    sources: &mut SourceMap,
+  ) -> elab::Program {
+    for import in program.imports.iter() {
+      if let Some(exports) = self.import(import, sources) {
+        self.bring_into_scope(&mut scope.env, &mut scope.fixities, import, &exports);
+      }
+    }
+
+    let program = operators::resolve(program, &mut scope.fixities, &self.reporter);
+    scope
+      .defined
+      .extend(program.declarations.iter().map(|decl| match decl {
+        TopLevel::Function(function) => Definition {
+          name: function.name.clone(),
+          public: function.public,
+          variants: None,
+          src: function.src.clone(),
+        },
+        TopLevel::Enum(r#enum) => Definition {
+          name: r#enum.name.clone(),
+          public: r#enum.public,
+          variants: Some(r#enum.variants.clone()),
+          src: r#enum.src.clone(),
+        },
+      }));
+
+    let ((env, program), _) = program.infer(scope.env.clone());
+    scope.env = env;
+    program
+  }
+
+  fn parse(&mut self, file: FileId, sources: &SourceMap) -> ast::Program {
+    let errors = self.reporter.counts().errors;
+    let program = parse::parse_program(file, &sources.get(file).text, &self.reporter);
+    self.parse_failed |= self.reporter.counts().errors > errors;
+    program
+  }
+
+  fn check(
+    &mut self,
+    module: Option<String>,
+    program: ast::Program,
+    sources: &mut SourceMap,
+  ) -> (Exports, Scope, elab::Program) {
+    let mut scope = self.empty_scope(module);
+    let declared = program
+      .fixities
+      .iter()
+      .map(|decl| (decl.operator.clone(), decl.fixity))
+      .collect::<HashMap<_, _>>();
+    let program = self.extend(&mut scope, program, sources);
+
+    let mut exports = Exports::default();
+    for definition in &scope.defined {
+      let name = definition.name.clone();
+      let link_name = scope.env.link_name(&name);
+      if !definition.public {
+        exports.private.insert(name);
+      } else if let Some(variants) = &definition.variants {
+        for variant in variants {
+          let variant_name = format!(".{}", variant.data);
+          exports.defined_at.insert(variant_name, variant.src.clone());
+        }
+        let variants = variants.iter().map(|variant| *variant.data.clone());
+        exports.enums.insert(name, (link_name, variants.collect()));
+      } else if let Some(scheme) = scope.env.let_decls.get(&name) {
+        exports
+          .defined_at
+          .insert(name.clone(), definition.src.clone());
+        if let Some(fixity) = declared.get(&name) {
+          exports.fixities.insert(name.clone(), *fixity);
+        }
+        exports.functions.insert(name, (link_name, scheme.clone()));
+      }
+    }
+
+    (exports, scope, program)
+  }
+
+  fn import(&mut self, import: &Import, sources: &mut SourceMap) -> Option<Exports> {
+    let module = &import.module;
+
+    if let Some(start) = self.loading.iter().position(|m| m == module) {
+      let mut cycle = self.loading[start..].to_vec();
+      cycle.push(module.clone());
+      self
+        .reporter
+        .report(ModuleError::Cycle(cycle, import.src.clone()));
+      return None;
+    }
+
+    if let Some(exports) = self.modules.get(module) {
+      return Some(exports.clone());
+    }
+
+    let file_name = format!("{module}.oktu");
+    let Some(path) = self
+      .search_path
+      .iter()
+      .map(|dir| dir.join(&file_name))
+      .find(|path| path.is_file())
+    else {
+      self.reporter.report(ModuleError::NotFound(
+        module.clone(),
+        self.search_path.clone(),
+        import.src.clone(),
+      ));
+      return None;
+    };
+
+    let input = match std::fs::read_to_string(&path) {
+      Ok(input) => input,
+      Err(e) => {
+        self.reporter.report(ModuleError::Unreadable(
+          module.clone(),
+          e.to_string(),
+          import.src.clone(),
+        ));
+        return None;
+      }
+    };
+
+    let file = sources.add(path.display().to_string(), input);
+    let program = self.parse(file, sources);
+
+    self.loading.push(module.clone());
+    let (exports, _, program) = self.check(Some(module.clone()), program, sources);
+    self.loading.pop();
+
+    self.declarations.extend(program.declarations);
+    self.modules.insert(module.clone(), exports.clone());
+    Some(exports)
+  }
+
+  fn bring_into_scope(
+    &self,
+    env: &mut Env,
+    fixities: &mut Fixities,
+    import: &Import,
+    exports: &Exports,
+  ) {
+    let module = &import.module;
+
+    let Some(names) = &import.names else {
+      for (name, (link_name, scheme)) in exports.functions.iter() {
+        let qualified = format!("{module}.{name}");
+        if let Some(src) = exports.defined_at.get(name) {
+          env.defined_at.insert(qualified.clone(), src.clone());
+        }
+        env.let_decls.insert(qualified.clone(), scheme.clone());
+        env.link_names.insert(qualified, link_name.clone());
+      }
+      for (link_name, variants) in exports.enums.values() {
+        import_enum(env, &exports.defined_at, link_name, variants);
+      }
+      for name in exports.private.iter() {
+        let qualified = format!("{module}.{name}");
+        env
+          .private
+          .insert(qualified, (module.clone(), name.clone()));
+      }
+      return;
+    };
+
+    for name in names {
+      if let Some((link_name, scheme)) = exports.functions.get(name) {
+        if let Some(src) = exports.defined_at.get(name) {
+          env.defined_at.insert(name.clone(), src.clone());
+        }
+        env.let_decls.insert(name.clone(), scheme.clone());
+        env.link_names.insert(name.clone(), link_name.clone());
+        if let Some(fixity) = exports.fixities.get(name) {
+          fixities.insert(name.clone(), *fixity);
+        }
+      } else if let Some((link_name, variants)) = exports.enums.get(name) {
+        env.link_names.insert(name.clone(), link_name.clone());
+        import_enum(env, &exports.defined_at, link_name, variants);
+      } else if exports.private.contains(name) {
+        self.reporter.report(ModuleError::Private(
+          module.clone(),
+          name.clone(),
+          import.src.clone(),
+        ));
+      } else {
+        self.reporter.report(ModuleError::UnknownItem(
+          module.clone(),
+          name.clone(),
+          import.src.clone(),
+        ));
+      }
+    }
+  }
+}
+
+fn import_enum(
+  env: &mut Env,
+  defined_at: &HashMap<String, Src>,
+  link_name: &str,
+  variants: &[String],
+) {
+  env.enum_decls.insert(link_name.to_string(), variants.len());
+  for variant in variants {
+    let variant_name = format!(".{variant}");
+    if let Some(src) = defined_at.get(&variant_name) {
+      env.defined_at.insert(variant_name, src.clone());
+    }
+    env
+      .variant_to_enum
+      .insert(variant.clone(), link_name.to_string());
+  }
+}
+
+impl Diagnostic for ModuleError {
+  fn message(&self) -> String {
+    match self {
+      ModuleError::NotFound(module, ..) => format!("Module '{module}' not found."),
+      ModuleError::Unreadable(module, ..) => format!("Could not read module '{module}'."),
+      ModuleError::Cycle(..) => "Import cycle detected.".to_string(),
+      ModuleError::Private(module, name, _) => {
+        format!("'{name}' is private to module '{module}'.")
+      }
+      ModuleError::UnknownItem(module, name, _) => {
+        format!("Module '{module}' has no item named '{name}'.")
+      }
+    }
+  }
+
+  fn severity(&self) -> Severity {
+    Severity::Error
+  }
+
+  fn code(&self) -> &'static str {
+    match self {
+      ModuleError::NotFound(..) => "module-not-found",
+      ModuleError::Unreadable(..) => "module-unreadable",
+      ModuleError::Cycle(..) => "import-cycle",
+      ModuleError::Private(..) => "private-item",
+      ModuleError::UnknownItem(..) => "unknown-item",
+    }
+  }
+
+  fn extra(&self) -> Vec<String> {
+    match self {
+      ModuleError::NotFound(module, search_path, _) => search_path
+        .iter()
+        .map(|dir| format!("Searched {}.", dir.join(format!("{module}.oktu")).display()))
+        .collect(),
+      ModuleError::Unreadable(_, error, _) => vec![error.clone()],
+      ModuleError::Cycle(cycle, _) => vec![format!("{}.", cycle.join(" -> "))],
+      ModuleError::Private(..) => vec!["Mark it with `pub` to export it.".to_string()],
+      ModuleError::UnknownItem(..) => vec![],
+    }
+  }
+
+  fn src(&self) -> Option<Src> {
+    match self {
+      ModuleError::NotFound(.., src) => Some(src.clone()),
+      ModuleError::Unreadable(.., src) => Some(src.clone()),
+      ModuleError::Cycle(_, src) => Some(src.clone()),
+      ModuleError::Private(.., src) => Some(src.clone()),
+      ModuleError::UnknownItem(.., src) => Some(src.clone()),
+    }
+  }
+}
@@ -0,0 +1,533 @@
+use std::collections::HashMap;
+
+use crate::{
+  ast::{
+    Arm, Assoc, Expression, ExpressionType, Fixity, Operation, Operator, PatternType, Program,
+    Spanned, Src, TopLevel,
+  },
+  report::{Diagnostic, Reporter, Severity},
+};
+
+/// The fixities of the builtin operators.
+const BUILTIN_FIXITIES: &[(&str, Assoc, u8)] = &[
+  ("**", Assoc::Right, 9),
+  ("*", Assoc::Left, 8),
+  ("/", Assoc::Left, 8),
+  ("%", Assoc::Left, 8),
+  ("+", Assoc::Left, 7),
+  ("-", Assoc::Left, 7),
+  ("<<", Assoc::Left, 6),
+  (">>", Assoc::Left, 6),
+  ("&", Assoc::Left, 6),
+  ("^", Assoc::Left, 6),
+  ("|", Assoc::Left, 6),
+  ("::", Assoc::Right, 5),
+  ("++", Assoc::Right, 5),
+  ("=", Assoc::Left, 4),
+  ("<>", Assoc::Left, 4),
+  ("<", Assoc::Left, 4),
+  ("<=", Assoc::Left, 4),
+  (">", Assoc::Left, 4),
+  (">=", Assoc::Left, 4),
+  ("&&", Assoc::Right, 3),
+  ("||", Assoc::Right, 2),
+];
+
+/// The fixity of operators that don't declare one.
+const DEFAULT_FIXITY: Fixity = Fixity {
+  assoc: Assoc::Left,
+  precedence: 9,
+};
+
+/// The parameters of the functions sections stand for. They can't be
+/// written in programs, and don't start with `_` so they aren't wildcards.
+const SECTION_LHS: &str = "oktu-lhs";
+const SECTION_RHS: &str = "oktu-rhs";
+
+/// The fixities of the operators in scope in a module.
+#[derive(Clone)]
+pub struct Fixities(HashMap<String, Fixity>);
+
+pub enum OperatorError {
+  /// Operators of the same precedence that don't associate the same way.
+  Ambiguous {
+    first: String,
+    second: String,
+    precedence: u8,
+    src: Src,
+  },
+  BuiltinFixity(String, Src),
+  BuiltinDefinition(String, Src),
+}
+
+impl Fixities {
+  pub fn new() -> Self {
+    let fixities = BUILTIN_FIXITIES
+      .iter()
+      .map(|&(operator, assoc, precedence)| (operator.to_string(), Fixity { assoc, precedence }))
+      .collect();
+    Self(fixities)
+  }
+
+  pub fn get(&self, operator: &str) -> Fixity {
+    self.0.get(operator).copied().unwrap_or(DEFAULT_FIXITY)
+  }
+
+  pub fn insert(&mut self, operator: String, fixity: Fixity) {
+    self.0.insert(operator, fixity);
+  }
+}
+
+impl Default for Fixities {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// Groups the operator chains and desugars the sections of a parsed
+/// program, with the fixities it declares added to `fixities`.
+pub fn resolve(program: Program, fixities: &mut Fixities, reporter: &Reporter) -> Program {
+  for decl in program.fixities.iter() {
+    if Operation::from_symbol(&decl.operator).is_some() {
+      reporter.report(OperatorError::BuiltinFixity(
+        decl.operator.clone(),
+        decl.src.clone(),
+      ));
+    } else {
+      fixities.insert(decl.operator.clone(), decl.fixity);
+    }
+  }
+
+  let resolver = Resolver { fixities, reporter };
+  let declarations = program
+    .declarations
+    .into_iter()
+    .map(|decl| match decl {
+      TopLevel::Function(mut function) => {
+        if Operation::from_symbol(&function.name).is_some() {
+          reporter.report(OperatorError::BuiltinDefinition(
+            function.name.clone(),
+            function.body.src(),
+          ));
+        }
+        function.body = resolver.expression(function.body);
+        TopLevel::Function(function)
+      }
+      decl @ TopLevel::Enum(_) => decl,
+    })
+    .collect();
+
+  Program {
+    declarations,
+    ..program
+  }
+}
+
+struct Resolver<'a> {
+  fixities: &'a Fixities,
+  reporter: &'a Reporter,
+}
+
+impl Resolver<'_> {
+  fn expression(&self, expression: Expression) -> Expression {
+    let src = expression.src;
+    let data = match *expression.data {
+      ExpressionType::Infix { first, rest } => return self.infix(*first, rest),
+      ExpressionType::Section {
+        operator,
+        left,
+        right,
+      } => return self.section(operator, left, right, src),
+      ExpressionType::Fun { parameters, body } => ExpressionType::Fun {
+        parameters,
+        body: self.boxed(*body),
+      },
+      ExpressionType::Application { function, argument } => ExpressionType::Application {
+        function: self.boxed(*function),
+        argument: self.boxed(*argument),
+      },
+      ExpressionType::Let { bind, value, next } => ExpressionType::Let {
+        bind,
+        value: self.boxed(*value),
+        next: self.boxed(*next),
+      },
+      ExpressionType::If {
+        condition,
+        then,
+        otherwise,
+      } => ExpressionType::If {
+        condition: self.boxed(*condition),
+        then: self.boxed(*then),
+        otherwise: self.boxed(*otherwise),
+      },
+      ExpressionType::Match { scrutinee, arms } => ExpressionType::Match {
+        scrutinee: self.boxed(*scrutinee),
+        arms: arms
+          .into_iter()
+          .map(|arm| Arm {
+            left: arm.left,
+            right: self.expression(arm.right),
+          })
+          .collect(),
+      },
+      ExpressionType::BinaryOp { op, lhs, rhs } => ExpressionType::BinaryOp {
+        op,
+        lhs: self.boxed(*lhs),
+        rhs: self.boxed(*rhs),
+      },
+      ExpressionType::UnaryOp { op, operand } => ExpressionType::UnaryOp {
+        op,
+        operand: self.boxed(*operand),
+      },
+      ExpressionType::Tuple { elements } => ExpressionType::Tuple {
+        elements: self.all(elements),
+      },
+      ExpressionType::List { elements } => ExpressionType::List {
+        elements: self.all(elements),
+      },
+      ExpressionType::Interpolation { parts } => ExpressionType::Interpolation {
+        parts: self.all(parts),
+      },
+      ExpressionType::Projection { tuple, index } => ExpressionType::Projection {
+        tuple: self.boxed(*tuple),
+        index,
+      },
+      data @ (ExpressionType::Unit
+      | ExpressionType::Hole { .. }
+      | ExpressionType::Variable { .. }
+      | ExpressionType::Literal { .. }
+      | ExpressionType::Variant { .. }) => data,
+    };
+    Spanned::new(data, src)
+  }
+
+  fn boxed(&self, expression: Expression) -> Box<Expression> {
+    Box::new(self.expression(expression))
+  }
+
+  fn all(&self, expressions: Vec<Expression>) -> Vec<Expression> {
+    expressions
+      .into_iter()
+      .map(|expression| self.expression(expression))
+      .collect()
+  }
+
+  /// Groups a chain of operators by precedence climbing, reducing the
+  /// operators on the stack that bind tighter than the next one.
+  fn infix(&self, first: Expression, rest: Vec<(Operator, Expression)>) -> Expression {
+    let mut operands = vec![self.expression(first)];
+    let mut operators: Vec<(Operator, Fixity)> = Vec::new();
+
+    for (operator, operand) in rest {
+      let fixity = self.fixities.get(&operator.data);
+      while let Some((top, top_fixity)) = operators.last() {
+        if top_fixity.precedence < fixity.precedence {
+          break;
+        }
+        if top_fixity.precedence == fixity.precedence {
+          match (top_fixity.assoc, fixity.assoc) {
+            (Assoc::Right, Assoc::Right) => break,
+            (Assoc::Left, Assoc::Left) => {}
+            _ => self.reporter.report(OperatorError::Ambiguous {
+              first: (*top.data).clone(),
+              second: (*operator.data).clone(),
+              precedence: fixity.precedence,
+              src: operator.src(),
+            }),
+          }
+        }
+        reduce(&mut operands, &mut operators);
+      }
+      operators.push((operator, fixity));
+      operands.push(self.expression(operand));
+    }
+
+    while !operators.is_empty() {
+      reduce(&mut operands, &mut operators);
+    }
+    operands
+      .pop()
+      .expect("a chain has one more operand than operators")
+  }
+
+  /// `(op)` is the operator as a function, `(op x)` and `(x op)` are it
+  /// applied to one side.
+  fn section(
+    &self,
+    operator: Operator,
+    left: Option<Box<Expression>>,
+    right: Option<Box<Expression>>,
+    src: Src,
+  ) -> Expression {
+    let variable = |name: &str| {
+      Spanned::new(
+        ExpressionType::Variable {
+          name: name.to_string(),
+        },
+        src.clone(),
+      )
+    };
+    let fun = |variable: &str, body: Expression| {
+      Spanned::new(
+        ExpressionType::Fun {
+          parameters: vec![Spanned::new(
+            PatternType::Variable {
+              name: variable.to_string(),
+            },
+            src.clone(),
+          )],
+          body: Box::new(body),
+        },
+        src.clone(),
+      )
+    };
+
+    match (left, right) {
+      (Some(left), _) => {
+        let body = binary(operator, self.expression(*left), variable(SECTION_RHS));
+        fun(SECTION_RHS, body)
+      }
+      (None, Some(right)) => {
+        let body = binary(operator, variable(SECTION_LHS), self.expression(*right));
+        fun(SECTION_LHS, body)
+      }
+      (None, None) if Operation::from_symbol(&operator.data).is_some() => {
+        let body = binary(operator, variable(SECTION_LHS), variable(SECTION_RHS));
+        fun(SECTION_LHS, fun(SECTION_RHS, body))
+      }
+      (None, None) => Spanned::new(
+        ExpressionType::Variable {
+          name: *operator.data,
+        },
+        src,
+      ),
+    }
+  }
+}
+
+fn reduce(operands: &mut Vec<Expression>, operators: &mut Vec<(Operator, Fixity)>) {
+  let (operator, _) = operators.pop().expect("reduced an empty stack");
+  let rhs = operands.pop().expect("operator without right operand");
+  let lhs = operands.pop().expect("operator without left operand");
+  operands.push(binary(operator, lhs, rhs));
+}
+
+/// `lhs op rhs`, a call of the operator's function unless it is builtin.
+fn binary(operator: Operator, lhs: Expression, rhs: Expression) -> Expression {
+  let src = Src::new(lhs.src.file, lhs.src.range.start, rhs.src.range.end);
+  match Operation::from_symbol(&operator.data) {
+    Some(op) => Spanned::new(
+      ExpressionType::BinaryOp {
+        op,
+        lhs: Box::new(lhs),
+        rhs: Box::new(rhs),
+      },
+      src,
+    ),
+    None => {
+      let partial_src = Src::new(lhs.src.file, lhs.src.range.start, operator.src.range.end);
+      let function = Spanned::new(
+        ExpressionType::Variable {
+          name: *operator.data,
+        },
+        operator.src,
+      );
+      let partial = Spanned::new(
+        ExpressionType::Application {
+          function: Box::new(function),
+          argument: Box::new(lhs),
+        },
+        partial_src,
+      );
+      Spanned::new(
+        ExpressionType::Application {
+          function: Box::new(partial),
+          argument: Box::new(rhs),
+        },
+        src,
+      )
+    }
+  }
+}
+
+/// The name of an operator's definition in Bend, which doesn't allow
+/// symbols in names. It has `-`s, which Oktu names can't, so that it never
+/// clashes with a definition named like it.
+pub fn mangle(name: &str) -> String {
+  if name.starts_with(|c: char| c.is_alphanumeric() || c == '_') {
+    return name.to_string();
+  }
+  let symbols = name
+    .chars()
+    .map(|c| match c {
+      '!' => "bang",
+      '$' => "dollar",
+      '%' => "percent",
+      '&' => "amp",
+      '*' => "star",
+      '+' => "plus",
+      '-' => "minus",
+      '/' => "slash",
+      ':' => "colon",
+      '<' => "less",
+      '=' => "equal",
+      '>' => "greater",
+      '@' => "at",
+      '^' => "caret",
+      '|' => "bar",
+      '~' => "tilde",
+      _ => "symbol",
+    })
+    .collect::<Vec<_>>();
+  format!("op-{}", symbols.join("-"))
+}
+
+impl Diagnostic for OperatorError {
+  fn message(&self) -> String {
+    match self {
+      OperatorError::Ambiguous { first, second, .. } => {
+        format!("Ambiguous use of `{first}` and `{second}`.")
+      }
+      OperatorError::BuiltinFixity(operator, _) => {
+        format!("Cannot change the fixity of the builtin operator `{operator}`.")
+      }
+      OperatorError::BuiltinDefinition(operator, _) => {
+        format!("Cannot redefine the builtin operator `{operator}`.")
+      }
+    }
+  }
+
+  fn severity(&self) -> Severity {
+    Severity::Error
+  }
+
+  fn code(&self) -> &'static str {
+    match self {
+      OperatorError::Ambiguous { .. } => "ambiguous-operators",
+      OperatorError::BuiltinFixity(..) => "builtin-operator",
+      OperatorError::BuiltinDefinition(..) => "builtin-operator",
+    }
+  }
+
+  fn extra(&self) -> Vec<String> {
+    match self {
+      OperatorError::Ambiguous { precedence, .. } => vec![format!(
+        "Both have precedence {precedence} but don't associate the same way, add parentheses."
+      )],
+      _ => vec![],
+    }
+  }
+
+  fn src(&self) -> Option<Src> {
+    match self {
+      OperatorError::Ambiguous { src, .. } => Some(src.clone()),
+      OperatorError::BuiltinFixity(_, src) => Some(src.clone()),
+      OperatorError::BuiltinDefinition(_, src) => Some(src.clone()),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{ast::Literal, parse::parse_expression, source::SourceMap};
+
+  /// `source` with its operators resolved, fully parenthesized, and the
+  /// messages of the errors found.
+  fn resolve(source: &str, declared: &[(&str, Assoc, u8)]) -> (String, Vec<String>) {
+    let mut fixities = Fixities::new();
+    for &(operator, assoc, precedence) in declared {
+      fixities.insert(operator.to_string(), Fixity { assoc, precedence });
+    }
+    let file = SourceMap::new().add("test.oktu".to_string(), source.to_string());
+    let (reporter, diagnostics) = Reporter::new();
+    let expression = parse_expression(file, source, &reporter).expect("parses");
+    let resolver = Resolver {
+      fixities: &fixities,
+      reporter: &reporter,
+    };
+    let shown = show(&resolver.expression(expression));
+    (shown, diagnostics.try_iter().map(|d| d.message()).collect())
+  }
+
+  fn show(expression: &Expression) -> String {
+    match &*expression.data {
+      ExpressionType::Variable { name } => name.clone(),
+      ExpressionType::Literal {
+        literal: Literal::Integer { value },
+      } => value.to_string(),
+      ExpressionType::BinaryOp { op, lhs, rhs } => format!("({} {op:?} {})", show(lhs), show(rhs)),
+      ExpressionType::Application { function, argument } => {
+        format!("({} {})", show(function), show(argument))
+      }
+      ExpressionType::Fun { parameters, body } => {
+        let parameters = parameters
+          .iter()
+          .map(|parameter| match &*parameter.data {
+            PatternType::Variable { name } => name.clone(),
+            _ => unreachable!("sections only bind variables"),
+          })
+          .collect::<Vec<_>>();
+        format!("(fun {} -> {})", parameters.join(" "), show(body))
+      }
+      _ => unreachable!("not used in these tests"),
+    }
+  }
+
+  #[test]
+  fn groups_by_precedence() {
+    let (shown, errors) = resolve("1 + 2 * 3 - 4 ** 2 ** 3", &[]);
+    assert_eq!(shown, "((1 Add (2 Mul 3)) Sub (4 Pow (2 Pow 3)))");
+    assert!(errors.is_empty());
+  }
+
+  #[test]
+  fn associates_declared_operators() {
+    let declared = [("<+>", Assoc::Right, 5), ("|>", Assoc::Left, 1)];
+    let (shown, errors) = resolve("x |> f <+> 1 <+> 2 + 3", &declared);
+    assert_eq!(shown, "((|> x) ((<+> f) ((<+> 1) (2 Add 3))))");
+    assert!(errors.is_empty());
+  }
+
+  #[test]
+  fn undeclared_operators_are_infixl_9() {
+    let (shown, errors) = resolve("a <&> b <&> c * d", &[]);
+    assert_eq!(shown, "(((<&> ((<&> a) b)) c) Mul d)");
+    assert!(errors.is_empty());
+  }
+
+  #[test]
+  fn reports_mixed_associativity() {
+    let declared = [("<+>", Assoc::Right, 7)];
+    let (_, errors) = resolve("1 + 2 <+> 3", &declared);
+    assert_eq!(errors, ["Ambiguous use of `+` and `<+>`."]);
+  }
+
+  #[test]
+  fn mangles_into_names_oktu_cannot_spell() {
+    assert_eq!(mangle("<+>"), "op-less-plus-greater");
+    assert_eq!(mangle("|>"), "op-bar-greater");
+    assert_eq!(mangle("op_less_plus_greater"), "op_less_plus_greater");
+  }
+
+  #[test]
+  fn desugars_sections() {
+    assert_eq!(
+      resolve("(+ 1)", &[]).0,
+      "(fun oktu-lhs -> (oktu-lhs Add 1))"
+    );
+    assert_eq!(
+      resolve("(10 -)", &[]).0,
+      "(fun oktu-rhs -> (10 Sub oktu-rhs))"
+    );
+    assert_eq!(
+      resolve("(*)", &[]).0,
+      "(fun oktu-lhs -> (fun oktu-rhs -> (oktu-lhs Mul oktu-rhs)))"
+    );
+    assert_eq!(resolve("(<+>)", &[]).0, "<+>");
+    assert_eq!(
+      resolve("(<+> 1)", &[]).0,
+      "(fun oktu-lhs -> ((<+> oktu-lhs) 1))"
+    );
+  }
+}
@@ -0,0 +1,239 @@
+use lalrpop_util::{lexer::Token, ErrorRecovery, ParseError};
+
+use self::comments::Comment;
+use crate::{
+  ast::{Expression, Program, Src},
+  parser,
+  report::{Diagnostic, Reporter, Severity},
+  source::FileId,
+};
+
+pub mod comments;
+pub mod string;
+
+type Error<'input> = ParseError<usize, Token<'input>, SyntaxError>;
+
+pub enum SyntaxError {
+  InvalidToken(Src),
+  UnexpectedEof(Vec<String>, Src),
+  UnexpectedToken(String, Vec<String>, Src),
+  ExtraToken(String, Src),
+  InvalidEscape(String, Src),
+  InvalidUnicode(String, Src),
+  UnclosedInterpolation(Src),
+  /// `{...}` in a string around something that isn't a name.
+  BadInterpolation(Src),
+  InterpolatedPattern(Src),
+  UnterminatedComment(Src),
+  /// A tuple index too large to be an index at all.
+  IndexTooLarge(Src),
+  Custom(String),
+}
+
+/// Parses a whole program, reporting every syntax error found.
+///
+/// Declarations and match arms that fail to parse are skipped, so the
+/// returned program contains only the parts that were recovered.
+pub fn parse_program(file: FileId, input: &str, reporter: &Reporter) -> Program {
+  parse_with_comments(file, input, reporter).0
+}
+
+/// Parses a whole program like [`parse_program`], also returning its
+/// comments in order.
+pub fn parse_with_comments(
+  file: FileId,
+  input: &str,
+  reporter: &Reporter,
+) -> (Program, Vec<Comment>) {
+  let (code, comments, comment_errors) = comments::strip(file, input);
+  for error in comment_errors {
+    reporter.report(error);
+  }
+
+  let mut errors = Vec::new();
+  let result = parser::ProgramParser::new().parse(file, &mut errors, &code);
+
+  for ErrorRecovery { error, .. } in errors {
+    reporter.report(SyntaxError::new(error, file, &code));
+  }
+
+  let program = match result {
+    Ok(program) => program,
+    Err(error) => {
+      reporter.report(SyntaxError::new(error, file, &code));
+      Program::empty()
+    }
+  };
+  (program, comments)
+}
+
+/// Parses a single expression, as typed at the REPL.
+pub fn parse_expression(file: FileId, input: &str, reporter: &Reporter) -> Option<Expression> {
+  let (code, _, comment_errors) = comments::strip(file, input);
+  for error in comment_errors {
+    reporter.report(error);
+  }
+
+  let mut errors = Vec::new();
+  let result = parser::ExpressionParser::new().parse(file, &mut errors, &code);
+
+  for ErrorRecovery { error, .. } in errors {
+    reporter.report(SyntaxError::new(error, file, &code));
+  }
+
+  match result {
+    Ok(expression) => Some(expression),
+    Err(error) => {
+      reporter.report(SyntaxError::new(error, file, &code));
+      None
+    }
+  }
+}
+
+impl SyntaxError {
+  fn new(error: Error, file: FileId, input: &str) -> Self {
+    match error {
+      ParseError::InvalidToken { location } => {
+        let len = input[location..].chars().next().map_or(1, char::len_utf8);
+        SyntaxError::InvalidToken(Src::new(file, location, location + len))
+      }
+      ParseError::UnrecognizedEof { location, expected } => {
+        // Points at the last character of the final token.
+        let start = input[..location]
+          .char_indices()
+          .next_back()
+          .map_or(location, |(i, _)| i);
+        SyntaxError::UnexpectedEof(expected, Src::new(file, start, location))
+      }
+      ParseError::UnrecognizedToken {
+        token: (s, Token(_, token), e),
+        expected,
+      } => SyntaxError::UnexpectedToken(token.to_string(), expected, Src::new(file, s, e)),
+      ParseError::ExtraToken {
+        token: (s, Token(_, token), e),
+      } => SyntaxError::ExtraToken(token.to_string(), Src::new(file, s, e)),
+      ParseError::User { error } => error,
+    }
+  }
+}
+
+/// Turns a terminal name generated by LALRPOP into something readable.
+fn describe_terminal(terminal: &str) -> String {
+  match terminal {
+    r##"r#"[0-9][0-9_]*"#"## => "number".to_string(),
+    r##"r#"0x[0-9a-fA-F][0-9a-fA-F_]*"#"## => "number".to_string(),
+    r##"r#"0b[01][01_]*"#"## => "number".to_string(),
+    r##"r#"[0-9][0-9_]*\\.[0-9][0-9_]*"#"## => "number".to_string(),
+    r##"r#"[\\p{XID_Start}_]\\p{XID_Continue}*'*"#"## => "identifier".to_string(),
+    r##"r#"[\\p{XID_Start}_]\\p{XID_Continue}*'*\\.[\\p{XID_Start}_]\\p{XID_Continue}*'*"#"## => {
+      "qualified name".to_string()
+    }
+    r##"r#"\"([^\"\\\\]|\\\\(.|\\n))*\""#"## => "string".to_string(),
+    r##"r#"r\"[^\"]*\""#"## => "string".to_string(),
+    r##"r#"r#\"([^\"]|\"+[^\"#])*\"+#"#"## => "string".to_string(),
+    r##"r#"'([^'\\\\\\n]|\\\\[^\\n]|\\\\u\\{[^}'\\n]*\\})'"#"## => "char".to_string(),
+    r##"r#"[!$%&*+\\-/:<=>@^|~]+"#"## => "operator".to_string(),
+    r##"r#"\\.[0-9]+(\\.[0-9]+)*"#"## => "projection".to_string(),
+    _ => match terminal.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+      Some(token) => format!("`{}`", token.replace("\\\"", "\"").replace("\\\\", "\\")),
+      None => terminal.to_string(),
+    },
+  }
+}
+
+fn describe_expected(expected: &[String]) -> Vec<String> {
+  let mut names = Vec::new();
+  for terminal in expected {
+    let name = describe_terminal(terminal);
+    if !names.contains(&name) {
+      names.push(name);
+    }
+  }
+
+  match names.as_slice() {
+    [] => vec![],
+    [name] => vec![format!("Expected {name}.")],
+    [init @ .., last] => vec![format!("Expected one of {} or {last}.", init.join(", "))],
+  }
+}
+
+impl Diagnostic for SyntaxError {
+  fn message(&self) -> String {
+    match self {
+      SyntaxError::InvalidToken(_) => "Invalid token.".to_string(),
+      SyntaxError::UnexpectedEof(..) => "Unexpected end of file.".to_string(),
+      SyntaxError::UnexpectedToken(token, ..) => format!("Unexpected `{token}`."),
+      SyntaxError::ExtraToken(token, _) => format!("Extra token `{token}`."),
+      SyntaxError::InvalidEscape(escape, _) => format!("Unknown escape sequence `{escape}`."),
+      SyntaxError::InvalidUnicode(escape, _) => format!("Invalid unicode escape `{escape}`."),
+      SyntaxError::UnclosedInterpolation(_) => "Unclosed `{` in string.".to_string(),
+      SyntaxError::BadInterpolation(_) => "Only names can be interpolated in strings.".to_string(),
+      SyntaxError::InterpolatedPattern(_) => "Patterns can't interpolate names.".to_string(),
+      SyntaxError::UnterminatedComment(_) => "Unterminated comment.".to_string(),
+      SyntaxError::IndexTooLarge(_) => "Tuple index too large.".to_string(),
+      SyntaxError::Custom(message) => message.clone(),
+    }
+  }
+
+  fn severity(&self) -> Severity {
+    Severity::Error
+  }
+
+  fn code(&self) -> &'static str {
+    match self {
+      SyntaxError::InvalidToken(_) => "invalid-token",
+      SyntaxError::UnexpectedEof(..) => "unexpected-eof",
+      SyntaxError::UnexpectedToken(..) => "unexpected-token",
+      SyntaxError::ExtraToken(..) => "extra-token",
+      SyntaxError::InvalidEscape(..) => "invalid-escape",
+      SyntaxError::InvalidUnicode(..) => "invalid-escape",
+      SyntaxError::UnclosedInterpolation(_) => "bad-interpolation",
+      SyntaxError::BadInterpolation(_) => "bad-interpolation",
+      SyntaxError::InterpolatedPattern(_) => "bad-interpolation",
+      SyntaxError::UnterminatedComment(_) => "unterminated-comment",
+      SyntaxError::IndexTooLarge(_) => "index-too-large",
+      SyntaxError::Custom(_) => "syntax-error",
+    }
+  }
+
+  fn extra(&self) -> Vec<String> {
+    match self {
+      SyntaxError::UnexpectedEof(expected, _) => describe_expected(expected),
+      SyntaxError::UnexpectedToken(_, expected, _) => describe_expected(expected),
+      SyntaxError::InvalidEscape(..) => vec![
+        "The escapes are `\\n`, `\\t`, `\\r`, `\\0`, `\\\\`, `\\\"`, `\\'`, `\\{`, `\\}` and `\\u{...}`."
+          .to_string(),
+      ],
+      SyntaxError::InvalidUnicode(..) => {
+        vec!["Write one to six hex digits of a character, as in `\\u{e9}`.".to_string()]
+      }
+      SyntaxError::UnclosedInterpolation(_) | SyntaxError::InterpolatedPattern(_) => {
+        vec!["Write `\\{` for a literal brace.".to_string()]
+      }
+      SyntaxError::UnterminatedComment(_) => {
+        vec!["Comments nest, so every `(*` needs its own `*)`.".to_string()]
+      }
+      SyntaxError::BadInterpolation(_) => {
+        vec!["Bind the value to a name with `let` first.".to_string()]
+      }
+      _ => vec![],
+    }
+  }
+
+  fn src(&self) -> Option<Src> {
+    match self {
+      SyntaxError::InvalidToken(src) => Some(src.clone()),
+      SyntaxError::UnexpectedEof(_, src) => Some(src.clone()),
+      SyntaxError::UnexpectedToken(_, _, src) => Some(src.clone()),
+      SyntaxError::ExtraToken(_, src) => Some(src.clone()),
+      SyntaxError::InvalidEscape(_, src) => Some(src.clone()),
+      SyntaxError::InvalidUnicode(_, src) => Some(src.clone()),
+      SyntaxError::UnclosedInterpolation(src) => Some(src.clone()),
+      SyntaxError::BadInterpolation(src) => Some(src.clone()),
+      SyntaxError::InterpolatedPattern(src) => Some(src.clone()),
+      SyntaxError::UnterminatedComment(src) => Some(src.clone()),
+      SyntaxError::IndexTooLarge(src) => Some(src.clone()),
+      SyntaxError::Custom(_) => None,
+    }
+  }
+}
@@ -0,0 +1,206 @@
+use crate::{ast::Src, source::FileId};
+
+use super::SyntaxError;
+
+/// A comment, with its delimiters.
+#[derive(Clone, Debug)]
+pub struct Comment {
+  pub text: String,
+  pub src: Src,
+}
+
+/// Blanks out the comments of `input`, as LALRPOP's lexer can't skip nested
+/// ones. They become spaces, keeping line breaks, so positions don't move.
+///
+/// `(*` followed by whitespace opens a block comment, so that `(*2)` is still
+/// a section, and a symbol made only of dashes, as `--`, starts a line
+/// comment. Strings and chars are skipped over.
+pub fn strip(file: FileId, input: &str) -> (String, Vec<Comment>, Vec<SyntaxError>) {
+  let bytes = input.as_bytes();
+  let mut blanks = Vec::new();
+  let mut errors = Vec::new();
+  let mut i = 0;
+
+  while i < bytes.len() {
+    i = match bytes[i] {
+      b'"' => skip_string(bytes, i + 1),
+      b'r' if !after_name(input, i) && matches!(bytes.get(i + 1), Some(b'"' | b'#')) => {
+        skip_raw_string(bytes, i + 1)
+      }
+      b'\'' if !after_name(input, i) => skip_char(input, i),
+      b'(' if opens_block(bytes, i) => {
+        let end = skip_block(bytes, i);
+        if end.is_none() {
+          errors.push(SyntaxError::UnterminatedComment(Src::new(file, i, i + 2)));
+        }
+        let end = end.unwrap_or(bytes.len());
+        blanks.push(i..end);
+        end
+      }
+      b if is_symbol(b) => {
+        let end = (i..bytes.len())
+          .find(|&j| !is_symbol(bytes[j]))
+          .unwrap_or(bytes.len());
+        if end - i >= 2 && bytes[i..end].iter().all(|&b| b == b'-') {
+          let end = (end..bytes.len())
+            .find(|&j| bytes[j] == b'\n')
+            .unwrap_or(bytes.len());
+          blanks.push(i..end);
+        }
+        end
+      }
+      _ => i + input[i..].chars().next().map_or(1, char::len_utf8),
+    };
+  }
+
+  let comments = blanks
+    .iter()
+    .map(|range| Comment {
+      text: input[range.clone()].trim_end().to_string(),
+      src: Src::new(file, range.start, range.end),
+    })
+    .collect();
+
+  let mut code = bytes.to_vec();
+  for range in blanks {
+    for byte in &mut code[range] {
+      if !matches!(byte, b'\n' | b'\r') {
+        *byte = b' ';
+      }
+    }
+  }
+  let code = String::from_utf8(code).expect("comments are blanked out whole");
+  (code, comments, errors)
+}
+
+/// A character that can be part of an operator.
+fn is_symbol(b: u8) -> bool {
+  b"!$%&*+-/:<=>@^|~".contains(&b)
+}
+
+fn opens_block(bytes: &[u8], i: usize) -> bool {
+  bytes[i..].starts_with(b"(*") && bytes.get(i + 2).is_some_and(u8::is_ascii_whitespace)
+}
+
+/// The end of the block comment starting at `i`, if it is closed.
+fn skip_block(bytes: &[u8], mut i: usize) -> Option<usize> {
+  let mut depth = 0;
+  while i < bytes.len() {
+    if opens_block(bytes, i) {
+      depth += 1;
+      i += 2;
+    } else if bytes[i..].starts_with(b"*)") {
+      depth -= 1;
+      i += 2;
+      if depth == 0 {
+        return Some(i);
+      }
+    } else {
+      i += 1;
+    }
+  }
+  None
+}
+
+/// Skips past the closing quote of a string whose body starts at `i`.
+fn skip_string(bytes: &[u8], mut i: usize) -> usize {
+  while i < bytes.len() {
+    match bytes[i] {
+      b'\\' => i += 2,
+      b'"' => return i + 1,
+      _ => i += 1,
+    }
+  }
+  bytes.len()
+}
+
+/// Skips a raw string, `"..."` or `#"..."#`, starting at `i`.
+fn skip_raw_string(bytes: &[u8], i: usize) -> usize {
+  let (body, close): (usize, &[u8]) = match bytes[i..].starts_with(b"#\"") {
+    true => (i + 2, b"\"#"),
+    false => (i + 1, b"\""),
+  };
+  (body..bytes.len())
+    .find(|&j| bytes[j..].starts_with(close))
+    .map_or(bytes.len(), |j| j + close.len())
+}
+
+/// Skips the char literal starting at `i`, or just its quote if it isn't
+/// one.
+fn skip_char(input: &str, i: usize) -> usize {
+  let rest = &input[i + 1..];
+  let close = match rest.chars().next() {
+    Some('\\') => rest
+      .char_indices()
+      .skip(2)
+      .take_while(|&(_, c)| c != '\n')
+      .find(|&(_, c)| c == '\'')
+      .map(|(j, _)| j),
+    Some(c) => rest[c.len_utf8()..]
+      .starts_with('\'')
+      .then_some(c.len_utf8()),
+    None => None,
+  };
+  close.map_or(i + 1, |close| i + close + 2)
+}
+
+/// Whether the character before `i` is part of a name, making a `'` there a
+/// prime and an `r` part of the name.
+fn after_name(input: &str, i: usize) -> bool {
+  input[..i]
+    .chars()
+    .next_back()
+    .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '\'')
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{report::Diagnostic, source::SourceMap};
+
+  /// The code left of `input`, the texts of its comments and the errors.
+  fn strip_all(input: &str) -> (String, Vec<String>, Vec<String>) {
+    let file = SourceMap::new().add("test.oktu".to_string(), input.to_string());
+    let (code, comments, errors) = strip(file, input);
+    let comments = comments.into_iter().map(|c| c.text).collect();
+    let errors = errors.iter().map(|e| e.message()).collect();
+    (code, comments, errors)
+  }
+
+  #[test]
+  fn blanks_nested_comments() {
+    let (code, comments, errors) = strip_all("a (* b (* c *) d *) e");
+    assert_eq!(code, "a                   e");
+    assert_eq!(comments, ["(* b (* c *) d *)"]);
+    assert!(errors.is_empty());
+  }
+
+  #[test]
+  fn keeps_line_breaks() {
+    let (code, _, _) = strip_all("a (* b\nc *) d -- e\nf");
+    assert_eq!(code, "a     \n     d     \nf");
+  }
+
+  #[test]
+  fn line_comments_are_dashes_only() {
+    let (code, comments, _) = strip_all("x --> y -- z\n1 - -2");
+    assert_eq!(code, "x --> y     \n1 - -2");
+    assert_eq!(comments, ["-- z"]);
+  }
+
+  #[test]
+  fn leaves_sections_and_literals() {
+    let input = "(*2) \"(* -- *)\" r#\"(* \"\"#  '\"' x' (* *)";
+    let (code, comments, _) = strip_all(input);
+    assert_eq!(code, input.replace("(* *)", "     "));
+    assert_eq!(comments, ["(* *)"]);
+  }
+
+  #[test]
+  fn reports_unterminated_comments() {
+    let (code, comments, errors) = strip_all("a (* b (* c *)");
+    assert_eq!(code, "a             ");
+    assert_eq!(comments, ["(* b (* c *)"]);
+    assert_eq!(errors, ["Unterminated comment."]);
+  }
+}
@@ -0,0 +1,338 @@
+use std::{iter::Peekable, str::CharIndices};
+
+use lalrpop_util::{lexer::Token, ErrorRecovery, ParseError};
+
+use crate::{
+  ast::{ExpressionType, Literal, Spanned, Src},
+  source::FileId,
+};
+
+use super::SyntaxError;
+
+pub type Errors<'input> = Vec<ErrorRecovery<usize, Token<'input>, SyntaxError>>;
+
+/// A piece of a string literal, with its position in the source.
+enum Part {
+  Text(String, Src),
+  Name(String, Src),
+}
+
+/// `"text {name} text"`, a plain literal unless it interpolates names.
+pub fn expression(token: &str, file: FileId, start: usize, errors: &mut Errors) -> ExpressionType {
+  let mut parts = decode(token, file, start, errors);
+  match parts.as_mut_slice() {
+    [] => ExpressionType::Literal {
+      literal: Literal::String {
+        value: String::new(),
+      },
+    },
+    [Part::Text(value, _)] => ExpressionType::Literal {
+      literal: Literal::String {
+        value: std::mem::take(value),
+      },
+    },
+    _ => ExpressionType::Interpolation {
+      parts: parts
+        .into_iter()
+        .map(|part| match part {
+          Part::Text(value, src) => Spanned::new(
+            ExpressionType::Literal {
+              literal: Literal::String { value },
+            },
+            src,
+          ),
+          Part::Name(name, src) => Spanned::new(ExpressionType::Variable { name }, src),
+        })
+        .collect(),
+    },
+  }
+}
+
+/// A string literal in a pattern, where nothing can be interpolated.
+pub fn pattern(token: &str, file: FileId, start: usize, errors: &mut Errors) -> Literal {
+  let mut value = String::new();
+  for part in decode(token, file, start, errors) {
+    match part {
+      Part::Text(text, _) => value.push_str(&text),
+      Part::Name(_, src) => report(errors, SyntaxError::InterpolatedPattern(src)),
+    }
+  }
+  Literal::String { value }
+}
+
+/// A char literal, `'a'` or `'\n'`.
+pub fn char(token: &str, file: FileId, start: usize, errors: &mut Errors) -> Literal {
+  let body = &token[1..token.len() - 1];
+  let at = |from: usize, to: usize| Src::new(file, start + 1 + from, start + 1 + to);
+  let mut chars = body.char_indices().peekable();
+  let value = match chars.next() {
+    Some((i, '\\')) => match unescape(body, i, &mut chars, at) {
+      Ok(c) => c.unwrap_or_default(),
+      Err(error) => {
+        report(errors, error);
+        '\0'
+      }
+    },
+    Some((_, c)) => c,
+    None => '\0',
+  };
+  Literal::Char { value }
+}
+
+/// Writes `value` as a string literal that decodes back to it.
+pub fn escape(value: &str) -> String {
+  let mut escaped = String::from('"');
+  for c in value.chars() {
+    push_escaped(&mut escaped, c, '"');
+  }
+  escaped.push('"');
+  escaped
+}
+
+/// Writes `c` as a char literal.
+pub fn escape_char(c: char) -> String {
+  let mut escaped = String::from('\'');
+  push_escaped(&mut escaped, c, '\'');
+  escaped.push('\'');
+  escaped
+}
+
+/// Pushes `c` escaped for a literal delimited by `quote`.
+fn push_escaped(escaped: &mut String, c: char, quote: char) {
+  match c {
+    '\n' => escaped.push_str("\\n"),
+    '\t' => escaped.push_str("\\t"),
+    '\r' => escaped.push_str("\\r"),
+    '\0' => escaped.push_str("\\0"),
+    '\\' => escaped.push_str("\\\\"),
+    '{' if quote == '"' => escaped.push_str("\\{"),
+    c if c == quote => {
+      escaped.push('\\');
+      escaped.push(c);
+    }
+    c if c.is_control() => escaped.push_str(&format!("\\u{{{:x}}}", c as u32)),
+    c => escaped.push(c),
+  }
+}
+
+fn report(errors: &mut Errors, error: SyntaxError) {
+  errors.push(ErrorRecovery {
+    error: ParseError::User { error },
+    dropped_tokens: Vec::new(),
+  });
+}
+
+/// Splits a string token into text and interpolated names, decoding its
+/// escapes. Raw strings, `r"..."` and `r#"..."#`, are taken as written.
+fn decode(token: &str, file: FileId, start: usize, errors: &mut Errors) -> Vec<Part> {
+  if let Some(raw) = token.strip_prefix('r') {
+    let hashes = raw.len() - raw.trim_start_matches('#').len();
+    let text = &raw[hashes + 1..raw.len() - hashes - 1];
+    let src = Src::new(file, start, start + token.len());
+    return vec![Part::Text(text.to_string(), src)];
+  }
+
+  let body = &token[1..token.len() - 1];
+  let at = |from: usize, to: usize| Src::new(file, start + 1 + from, start + 1 + to);
+  let mut parts = Vec::new();
+  let mut text = String::new();
+  let mut text_start = 0;
+  let mut chars = body.char_indices().peekable();
+
+  while let Some((i, c)) = chars.next() {
+    match c {
+      '\\' => match unescape(body, i, &mut chars, at) {
+        Ok(Some(c)) => text.push(c),
+        Ok(None) => {}
+        Err(error) => report(errors, error),
+      },
+      '{' => {
+        let Some(close) = body[i..].find('}').map(|close| i + close) else {
+          report(errors, SyntaxError::UnclosedInterpolation(at(i, i + 1)));
+          text.push(c);
+          continue;
+        };
+        if !text.is_empty() {
+          parts.push(Part::Text(std::mem::take(&mut text), at(text_start, i)));
+        }
+
+        let inner = &body[i + 1..close];
+        let name = inner.trim();
+        if is_name(name) {
+          let name_start = i + 1 + (inner.len() - inner.trim_start().len());
+          let src = at(name_start, name_start + name.len());
+          parts.push(Part::Name(name.to_string(), src));
+        } else {
+          report(errors, SyntaxError::BadInterpolation(at(i, close + 1)));
+        }
+
+        while chars.next_if(|(j, _)| *j <= close).is_some() {}
+        text_start = close + 1;
+      }
+      c => text.push(c),
+    }
+  }
+
+  if !text.is_empty() {
+    parts.push(Part::Text(text, at(text_start, body.len())));
+  }
+  parts
+}
+
+type Chars<'a> = Peekable<CharIndices<'a>>;
+
+/// Decodes the escape starting with the `\\` at `i` of `body`, `None` being a
+/// line continuation. `at` locates a range of `body` in the source.
+fn unescape(
+  body: &str,
+  i: usize,
+  chars: &mut Chars,
+  at: impl Fn(usize, usize) -> Src,
+) -> Result<Option<char>, SyntaxError> {
+  let Some((_, escape)) = chars.next() else {
+    return Ok(None);
+  };
+  let decoded = match escape {
+    'n' => '\n',
+    't' => '\t',
+    'r' => '\r',
+    '0' => '\0',
+    '\\' | '"' | '\'' | '{' | '}' => escape,
+    // A line continuation, skipping the indentation of the next line.
+    '\n' | '\r' => {
+      while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+      return Ok(None);
+    }
+    'u' => {
+      let rest = &body[i + 2..];
+      let len = match rest.find('}') {
+        Some(close) if rest.starts_with('{') => close + 1,
+        _ => 0,
+      };
+      let end = i + 2 + len;
+      while chars.next_if(|(j, _)| *j < end).is_some() {}
+      let decoded = rest
+        .get(1..len.saturating_sub(1))
+        .filter(|code| (1..=6).contains(&code.len()))
+        .filter(|code| code.chars().all(|c| c.is_ascii_hexdigit()))
+        .and_then(|code| u32::from_str_radix(code, 16).ok())
+        .and_then(char::from_u32);
+      match decoded {
+        Some(c) => c,
+        None => {
+          let escape = body[i..end].to_string();
+          return Err(SyntaxError::InvalidUnicode(escape, at(i, end)));
+        }
+      }
+    }
+    _ => {
+      let end = i + 1 + escape.len_utf8();
+      let escape = body[i..end].to_string();
+      return Err(SyntaxError::InvalidEscape(escape, at(i, end)));
+    }
+  };
+  Ok(Some(decoded))
+}
+
+/// `name` or `module.name`.
+fn is_name(s: &str) -> bool {
+  let is_ident = |s: &str| {
+    let s = s.trim_end_matches('\'');
+    s.starts_with(|c: char| c.is_alphabetic() || c == '_')
+      && s.chars().all(|c| c.is_alphanumeric() || c == '_')
+  };
+  match s.split_once('.') {
+    Some((module, name)) => is_ident(module) && is_ident(name),
+    None => is_ident(s),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{report::Diagnostic, source::SourceMap};
+
+  fn file() -> FileId {
+    SourceMap::new().add("test.oktu".to_string(), String::new())
+  }
+
+  /// The parts of the string `token`, names in braces, and the errors found
+  /// with where they are.
+  fn parts(token: &str) -> (Vec<String>, Vec<(String, usize)>) {
+    let mut errors = Vec::new();
+    let parts = decode(token, file(), 0, &mut errors)
+      .into_iter()
+      .map(|part| match part {
+        Part::Text(text, _) => text,
+        Part::Name(name, _) => format!("{{{name}}}"),
+      })
+      .collect();
+    let errors = errors
+      .into_iter()
+      .map(|ErrorRecovery { error, .. }| match error {
+        ParseError::User { error } => (error.message(), error.src().unwrap().range.start),
+        _ => unreachable!("only user errors are reported"),
+      })
+      .collect();
+    (parts, errors)
+  }
+
+  #[test]
+  fn decodes_escapes() {
+    let (parts, errors) = parts(r#""a\n\t\"\\\{\u{e9}\0""#);
+    assert_eq!(parts, ["a\n\t\"\\{é\0"]);
+    assert!(errors.is_empty());
+  }
+
+  #[test]
+  fn continues_lines() {
+    let (parts, _) = parts("\"one \\\n    two\"");
+    assert_eq!(parts, ["one two"]);
+  }
+
+  #[test]
+  fn reports_bad_escapes() {
+    let (parts, errors) = parts(r#""a\qb\u{110000}c""#);
+    assert_eq!(parts, ["abc"]);
+    assert_eq!(
+      errors,
+      [
+        ("Unknown escape sequence `\\q`.".to_string(), 2),
+        ("Invalid unicode escape `\\u{110000}`.".to_string(), 5),
+      ]
+    );
+  }
+
+  #[test]
+  fn takes_raw_strings_as_written() {
+    assert_eq!(parts(r#"r"\n{x}""#).0, ["\\n{x}"]);
+    assert_eq!(parts(r##"r#"say "hi""#"##).0, ["say \"hi\""]);
+  }
+
+  #[test]
+  fn splits_interpolations() {
+    let (parts, errors) = parts(r#""Hi {name}, {  m.x' }!""#);
+    assert_eq!(parts, ["Hi ", "{name}", ", ", "{m.x'}", "!"]);
+    assert!(errors.is_empty());
+  }
+
+  #[test]
+  fn reports_bad_interpolations() {
+    let (_, errors) = parts(r#""{1 + 2} {open""#);
+    assert_eq!(
+      errors,
+      [
+        ("Only names can be interpolated in strings.".to_string(), 1),
+        ("Unclosed `{` in string.".to_string(), 9),
+      ]
+    );
+  }
+
+  #[test]
+  fn escapes_back() {
+    let value = "a\n\"{é}\u{7}";
+    let (parts, _) = parts(&escape(value));
+    assert_eq!(parts, [value]);
+    assert_eq!(escape_char('\''), r"'\''");
+  }
+}
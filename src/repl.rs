@@ -0,0 +1,517 @@
+use std::{
+  collections::HashMap,
+  io::{BufRead, Write},
+  path::{Path, PathBuf},
+  sync::mpsc::Receiver,
+};
+
+use bend::fun::{FanKind, Num, Term};
+
+use crate::{
+  ast::{self, Declaration, Function, Src, TopLevel},
+  checker::types::{Type, TypeKind},
+  elab::{self, to_bend},
+  format,
+  module::{Loader, Scope},
+  parse::{
+    self,
+    string::{escape, escape_char},
+  },
+  report::{sink::Sink, Diagnostic, Reporter, Severity},
+  source::{FileId, SourceMap},
+};
+
+const PROMPT: &str = "oktu> ";
+/// Shown while an input continues on the next line.
+const CONTINUATION: &str = "  ... ";
+/// The name an expression typed at the prompt is defined as.
+const IT: &str = "it";
+
+const HELP: &str = "\
+Type a declaration to define it, or an expression to see its value and type.
+An input that isn't finished continues on the next line, and an empty line
+ends it anyway.
+
+  :type <expr>    Shows the type of an expression.
+  :load <file>    Starts over with the definitions of a file.
+  :reload         Loads the last file again.
+  :browse         Lists the definitions made so far.
+  :help           Shows this help.
+  :quit           Exits.";
+
+pub enum ReplError {
+  UnknownCommand(String),
+  MissingArgument(&'static str),
+  NothingLoaded,
+  Unreadable(PathBuf, String),
+  Compilation(String),
+  Evaluation(String),
+}
+
+/// An interactive session, checking each input in the scope left by the
+/// ones before it.
+pub struct Repl {
+  loader: Loader,
+  /// Where imports are searched for, besides the loaded file's directory.
+  search_path: Vec<PathBuf>,
+  prelude: bool,
+  sources: SourceMap,
+  reporter: Reporter,
+  diagnostics: Receiver<Box<dyn Diagnostic>>,
+  sink: Box<dyn Sink>,
+  scope: Scope,
+  /// Every declaration checked so far, in order, to link evaluations with.
+  declarations: Vec<elab::TopLevel>,
+  /// The file `:reload` reads again.
+  loaded: Option<PathBuf>,
+  /// How many inputs were checked, each being a module of its own so that
+  /// redefinitions don't clash with the definitions they shadow.
+  inputs: usize,
+}
+
+/// An input, read as declarations or as an expression.
+enum Parsed {
+  Declarations(ast::Program),
+  Expression(ast::Expression),
+  Incomplete,
+  Invalid,
+}
+
+impl Repl {
+  pub fn new(search_path: Vec<PathBuf>, prelude: bool, sink: Box<dyn Sink>) -> Self {
+    let (reporter, diagnostics) = Reporter::new();
+    let mut sources = SourceMap::new();
+    let mut loader = Loader::new(search_path.clone(), reporter.clone());
+    if prelude {
+      loader.load_prelude(&mut sources);
+    }
+    let scope = loader.empty_scope(None);
+    let declarations = loader.take_declarations();
+    Self {
+      loader,
+      search_path,
+      prelude,
+      sources,
+      reporter,
+      diagnostics,
+      sink,
+      scope,
+      declarations,
+      loaded: None,
+      inputs: 0,
+    }
+  }
+
+  /// Reads inputs from `input` until it ends or `:quit`.
+  pub fn run(&mut self, input: impl BufRead) -> std::io::Result<()> {
+    let mut lines = input.lines();
+    let mut buffer = String::new();
+    let mut file = None;
+
+    loop {
+      let prompt = if buffer.is_empty() {
+        PROMPT
+      } else {
+        CONTINUATION
+      };
+      print!("{prompt}");
+      std::io::stdout().flush()?;
+      let Some(line) = lines.next() else {
+        println!();
+        return Ok(());
+      };
+      let line = line?;
+
+      if buffer.is_empty() {
+        if line.trim().is_empty() {
+          continue;
+        }
+        if let Some(command) = line.trim().strip_prefix(':') {
+          if !self.command(command) {
+            return Ok(());
+          }
+          self.flush();
+          continue;
+        }
+      }
+
+      let complete = !buffer.is_empty() && line.trim().is_empty();
+      buffer.push_str(&line);
+      buffer.push('\n');
+      let id = *file.get_or_insert_with(|| self.sources.add("<repl>".to_string(), String::new()));
+      self.sources.update(id, buffer.clone());
+
+      match self.parse(id, &buffer, complete) {
+        Parsed::Incomplete => continue,
+        Parsed::Declarations(program) => _ = self.define(program),
+        Parsed::Expression(expression) => self.evaluate(expression),
+        Parsed::Invalid => {}
+      }
+      self.flush();
+      buffer.clear();
+      file = None;
+    }
+  }
+
+  /// Runs `:command`, returning whether to go on.
+  fn command(&mut self, command: &str) -> bool {
+    let (name, argument) = command
+      .split_once(char::is_whitespace)
+      .unwrap_or((command, ""));
+    let argument = argument.trim();
+    match name {
+      "q" | "quit" => return false,
+      "h" | "help" | "?" => println!("{HELP}"),
+      "t" | "type" if argument.is_empty() => self
+        .reporter
+        .report(ReplError::MissingArgument("an expression")),
+      "t" | "type" => {
+        let file = self.sources.add("<repl>".to_string(), argument.to_string());
+        if let Some(expression) = parse::parse_expression(file, argument, &self.reporter) {
+          // Checked like an evaluation, and then forgotten.
+          let (scope, declarations) = (self.scope.clone(), self.declarations.len());
+          if let Some(t) = self.define_it(expression) {
+            println!("{}", self.spell(&t));
+          }
+          self.scope = scope;
+          self.declarations.truncate(declarations);
+        }
+      }
+      "l" | "load" if argument.is_empty() => {
+        self.reporter.report(ReplError::MissingArgument("a file"))
+      }
+      "l" | "load" => self.load(PathBuf::from(argument)),
+      "r" | "reload" => match self.loaded.clone() {
+        Some(path) => self.load(path),
+        None => self.reporter.report(ReplError::NothingLoaded),
+      },
+      "b" | "browse" => self.browse(),
+      _ => self
+        .reporter
+        .report(ReplError::UnknownCommand(name.to_string())),
+    }
+    true
+  }
+
+  /// Reads `text` as declarations or as an expression, whichever parses,
+  /// reporting the errors of the one that got further otherwise. Unless it
+  /// is `complete`, input that ends too early is waited on.
+  fn parse(&mut self, file: FileId, text: &str, complete: bool) -> Parsed {
+    let (scratch, diagnostics) = Reporter::new();
+    let first_error = || {
+      diagnostics
+        .try_iter()
+        .map(|diagnostic| {
+          let at = diagnostic.src().map_or(0, |src| src.range.start);
+          let incomplete = matches!(diagnostic.code(), "unexpected-eof" | "unterminated-comment");
+          (at, incomplete)
+        })
+        .min_by_key(|&(at, _)| at)
+    };
+
+    let program = parse::parse_program(file, text, &scratch);
+    let Some(program_error) = first_error() else {
+      return Parsed::Declarations(program);
+    };
+    let expression = parse::parse_expression(file, text, &scratch);
+    let Some(expression_error) = first_error() else {
+      return expression.map_or(Parsed::Invalid, Parsed::Expression);
+    };
+
+    let (_, incomplete) = program_error.max(expression_error);
+    if incomplete && !complete {
+      return Parsed::Incomplete;
+    }
+    if program_error > expression_error {
+      parse::parse_program(file, text, &self.reporter);
+    } else {
+      parse::parse_expression(file, text, &self.reporter);
+    }
+    Parsed::Invalid
+  }
+
+  /// Checks `program` in the session's scope, keeping its definitions if it
+  /// has no errors. Returns whether it had none.
+  fn define(&mut self, program: ast::Program) -> bool {
+    let errors = self.reporter.counts().errors;
+    let saved = self.scope.clone();
+    self.inputs += 1;
+    self.scope.env.module = Some(format!("repl{}", self.inputs));
+
+    let program = self
+      .loader
+      .extend(&mut self.scope, program, &mut self.sources);
+    let imported = self.loader.take_declarations();
+    if self.reporter.counts().errors > errors {
+      // Constraints left by the failed input would be checked later.
+      self.scope = saved;
+      self.scope.env.numeric.borrow_mut().clear();
+      return false;
+    }
+
+    self.declarations.extend(imported);
+    self.declarations.extend(program.declarations);
+    true
+  }
+
+  /// Defines `expression` as `it`, returning its type.
+  fn define_it(&mut self, expression: ast::Expression) -> Option<Type> {
+    let src = expression.src.clone();
+    let function = Function {
+      public: false,
+      name: IT.to_string(),
+      rec: false,
+      parameters: vec![],
+      body: expression,
+      src,
+    };
+    let program = ast::Program::new(
+      vec![],
+      vec![Declaration::TopLevel(TopLevel::Function(function))],
+    );
+    if !self.define(program) {
+      return None;
+    }
+    self
+      .scope
+      .env
+      .let_decls
+      .get(IT)
+      .map(|scheme| scheme.t.clone())
+  }
+
+  /// Prints the value and type of `expression`, which becomes `it`.
+  fn evaluate(&mut self, expression: ast::Expression) {
+    let Some(t) = self.define_it(expression) else {
+      return;
+    };
+    let spelled = self.spell(&t);
+    if let TypeKind::Arrow { .. } = &*t.clone().resolve() {
+      println!("<fun> : {spelled}");
+      return;
+    }
+
+    // Only what `it` uses is linked, so that an earlier input Bend can't
+    // compile doesn't stop the ones after it.
+    let entrypoint = self.scope.env.link_name(IT);
+    let program = elab::Program {
+      file_name: None,
+      declarations: self.declarations.clone(),
+    };
+    let result = match program.reachable(&entrypoint).to_bend() {
+      Ok(book) => to_bend::run(book, &entrypoint).map_err(ReplError::Evaluation),
+      Err(error) => Err(ReplError::Compilation(error)),
+    };
+    match result {
+      Ok(term) => println!("{} : {spelled}", show(&term, &t)),
+      Err(error) => {
+        self.reporter.report(error);
+        println!("{IT} : {spelled}");
+      }
+    }
+  }
+
+  /// Starts over with the definitions of the file at `path`.
+  fn load(&mut self, path: PathBuf) {
+    let mut search_path = vec![path.parent().unwrap_or(Path::new(".")).to_path_buf()];
+    search_path.extend(self.search_path.iter().cloned());
+    let mut loader = Loader::new(search_path, self.reporter.clone());
+    if self.prelude {
+      loader.load_prelude(&mut self.sources);
+    }
+
+    // Checked as a module of its own, so its `main` doesn't clash with the
+    // entrypoints of evaluations.
+    let module = path
+      .file_stem()
+      .map(|stem| stem.to_string_lossy().into_owned());
+    let errors = self.reporter.counts().errors;
+    let (scope, program) = match loader.load_root_scope(&path, module, &mut self.sources) {
+      Ok(loaded) => loaded,
+      Err(error) => {
+        self
+          .reporter
+          .report(ReplError::Unreadable(path, error.to_string()));
+        return;
+      }
+    };
+    self.loaded = Some(path.clone());
+    if self.reporter.counts().errors > errors {
+      return;
+    }
+
+    println!("Loaded {}.", path.display());
+    self.loader = loader;
+    self.scope = scope;
+    self.declarations = program.declarations;
+    self.inputs = 0;
+  }
+
+  /// Prints the definitions of the loaded file and the session, the last
+  /// one of each name.
+  fn browse(&self) {
+    let defined = &self.scope.defined;
+    for (i, definition) in defined.iter().enumerate() {
+      let shadowed = defined[i + 1..].iter().any(|d| d.name == definition.name);
+      if shadowed || definition.name == IT {
+        continue;
+      }
+      match &definition.variants {
+        Some(variants) => {
+          let variants = variants.iter().map(|v| format!(".{}", v.data));
+          let variants = variants.collect::<Vec<_>>();
+          println!("enum {} := {}", definition.name, variants.join(", "));
+        }
+        None => {
+          if let Some(scheme) = self.scope.env.let_decls.get(&definition.name) {
+            let t = self.spell(&scheme.t);
+            println!("{} : {t}", format::name(&definition.name));
+          }
+        }
+      }
+    }
+  }
+
+  /// `t` as written in the session, its enums by the names they have in
+  /// scope rather than by the modules of the inputs that defined them.
+  fn spell(&self, t: &Type) -> Type {
+    let mut names: HashMap<&str, &str> = HashMap::new();
+    for (name, link_name) in &self.scope.env.link_names {
+      let shorter = |known: &&str| (name.len(), name.as_str()) < (known.len(), *known);
+      if names.get(link_name.as_str()).is_none_or(shorter) {
+        names.insert(link_name, name);
+      }
+    }
+    rename_enums(t, &names)
+  }
+
+  fn flush(&mut self) {
+    Reporter::drain(&self.diagnostics, &self.sources, self.sink.as_mut());
+  }
+}
+
+/// `t` with the enums in `names` renamed, following bound holes.
+fn rename_enums(t: &Type, names: &HashMap<&str, &str>) -> Type {
+  let t = t.clone().resolve();
+  match &*t {
+    TypeKind::Enum { name } => match names.get(name.as_str()) {
+      Some(name) => Type::new(TypeKind::Enum {
+        name: name.to_string(),
+      }),
+      None => t,
+    },
+    TypeKind::Arrow { t1, t2 } => Type::new(TypeKind::Arrow {
+      t1: rename_enums(t1, names),
+      t2: rename_enums(t2, names),
+    }),
+    TypeKind::Tuple { elements } => Type::new(TypeKind::Tuple {
+      elements: elements.iter().map(|e| rename_enums(e, names)).collect(),
+    }),
+    TypeKind::List { element } => Type::new(TypeKind::List {
+      element: rename_enums(element, names),
+    }),
+    _ => t,
+  }
+}
+
+/// `term`, the value of an expression of type `t`, written as in Oktu.
+fn show(term: &Term, t: &Type) -> String {
+  let t = t.clone().resolve();
+  match (&*t, term) {
+    (TypeKind::Unit, _) => "()".to_string(),
+    (TypeKind::Boolean, Term::Num { val: Num::U24(n) }) => (*n != 0).to_string(),
+    (TypeKind::Char, Term::Num { val: Num::U24(n) }) => {
+      char::from_u32(*n).map_or_else(|| n.to_string(), escape_char)
+    }
+    (TypeKind::Tuple { elements }, _) => {
+      // Bend builds tuples out of pairs, `(a, (b, c))`.
+      let mut terms = Vec::new();
+      let mut rest = term;
+      while terms.len() + 1 < elements.len() {
+        let Term::Fan {
+          fan: FanKind::Tup,
+          els,
+          ..
+        } = rest
+        else {
+          return term.to_string();
+        };
+        let Some((last, init)) = els.split_last() else {
+          return term.to_string();
+        };
+        terms.extend(init);
+        rest = last;
+      }
+      terms.push(rest);
+      if terms.len() != elements.len() {
+        return term.to_string();
+      }
+
+      let shown = terms
+        .into_iter()
+        .zip(elements)
+        .map(|(e, t)| show(e, t))
+        .collect::<Vec<_>>();
+      match shown.as_slice() {
+        [single] => format!("({single},)"),
+        _ => format!("({})", shown.join(", ")),
+      }
+    }
+    (_, Term::Num { val: Num::U24(n) }) => n.to_string(),
+    (_, Term::Num { val: Num::I24(n) }) => n.to_string(),
+    (_, Term::Num { val: Num::F24(n) }) if n.fract() == 0.0 && n.is_finite() => format!("{n}.0"),
+    (_, Term::Num { val: Num::F24(n) }) => n.to_string(),
+    (TypeKind::String, Term::Str { val }) => escape(val),
+    (TypeKind::List { element }, Term::List { els }) => {
+      let elements = els.iter().map(|e| show(e, element)).collect::<Vec<_>>();
+      format!("[{}]", elements.join(", "))
+    }
+    (TypeKind::Arrow { .. }, _) => "<fun>".to_string(),
+    _ => term.to_string(),
+  }
+}
+
+impl Diagnostic for ReplError {
+  fn message(&self) -> String {
+    match self {
+      ReplError::UnknownCommand(name) => format!("Unknown command `:{name}`."),
+      ReplError::MissingArgument(what) => format!("The command needs {what}."),
+      ReplError::NothingLoaded => "No file was loaded yet.".to_string(),
+      ReplError::Unreadable(path, error) => format!("Could not read {}: {error}", path.display()),
+      ReplError::Compilation(error) => format!("Could not compile the expression to Bend: {error}"),
+      ReplError::Evaluation(error) => format!("Could not evaluate the expression: {error}"),
+    }
+  }
+
+  fn severity(&self) -> Severity {
+    Severity::Error
+  }
+
+  fn code(&self) -> &'static str {
+    match self {
+      ReplError::UnknownCommand(_) => "unknown-command",
+      ReplError::MissingArgument(_) => "missing-argument",
+      ReplError::NothingLoaded => "nothing-loaded",
+      ReplError::Unreadable(..) => "unreadable-file",
+      ReplError::Compilation(_) => "compilation-failed",
+      ReplError::Evaluation(_) => "evaluation-failed",
+    }
+  }
+
+  fn extra(&self) -> Vec<String> {
+    match self {
+      ReplError::UnknownCommand(_) | ReplError::MissingArgument(_) => {
+        vec!["Type `:help` for the commands.".to_string()]
+      }
+      ReplError::NothingLoaded => vec!["Load one with `:load <file>`.".to_string()],
+      ReplError::Unreadable(..) | ReplError::Compilation(_) => vec![],
+      ReplError::Evaluation(_) => {
+        vec!["Values are computed by Bend, which needs `hvm`.".to_string()]
+      }
+    }
+  }
+
+  fn src(&self) -> Option<Src> {
+    None
+  }
+}
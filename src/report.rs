@@ -1,16 +1,28 @@
+pub mod render;
+pub mod sink;
+
 use core::fmt;
-use std::{
-  io::{Read, Seek, SeekFrom},
-  sync::mpsc,
-};
+use std::{cell::Cell, rc::Rc, sync::mpsc};
+
+use crate::{ast::Src, source::SourceMap};
 
-use crate::ast::Src;
+use self::sink::Sink;
 
 #[derive(Clone)]
 pub struct Reporter {
   sender: mpsc::Sender<Box<dyn Diagnostic>>,
+  counts: Rc<Cell<Counts>>,
 }
 
+/// How many diagnostics of each severity were reported.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Counts {
+  pub errors: usize,
+  pub warnings: usize,
+  pub infos: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Severity {
   Error,
   Warning,
@@ -22,38 +34,65 @@ pub trait Diagnostic {
 
   fn severity(&self) -> Severity;
 
+  /// A stable identifier for the kind of diagnostic, e.g. `type-mismatch`.
+  fn code(&self) -> &'static str;
+
   fn extra(&self) -> Vec<String>;
 
   fn src(&self) -> Option<Src>;
+
+  /// Secondary spans with a short explanation each.
+  fn labels(&self) -> Vec<(Src, String)> {
+    vec![]
+  }
 }
 
 impl Reporter {
   pub fn new() -> (Self, mpsc::Receiver<Box<dyn Diagnostic>>) {
     let (sender, recv) = mpsc::channel();
 
-    (Self { sender }, recv)
+    let counts = Rc::default();
+    (Self { sender, counts }, recv)
   }
 
   pub fn report(&self, diag: impl Diagnostic + 'static) {
+    let mut counts = self.counts.get();
+    match diag.severity() {
+      Severity::Error => counts.errors += 1,
+      Severity::Warning => counts.warnings += 1,
+      Severity::Info => counts.infos += 1,
+    }
+    self.counts.set(counts);
     self.sender.send(Box::new(diag)).unwrap()
   }
 
-  pub fn to_stdout(recv: mpsc::Receiver<Box<dyn Diagnostic>>, mut file: std::fs::File) {
-    const IDENT_SIZE: usize = 2;
+  pub fn counts(&self) -> Counts {
+    self.counts.get()
+  }
+
+  pub fn has_errors(&self) -> bool {
+    self.counts.get().errors > 0
+  }
+
+  /// Hands every diagnostic reported so far to `sink`.
+  pub fn drain(
+    recv: &mpsc::Receiver<Box<dyn Diagnostic>>,
+    sources: &SourceMap,
+    sink: &mut dyn Sink,
+  ) {
     for diagnostic in recv.try_iter() {
-      eprintln!("[{}]: {}", diagnostic.severity(), diagnostic.message());
-      if let Some(Src(pos)) = diagnostic.src() {
-        file.seek(SeekFrom::Start(pos.start as u64)).unwrap();
-        let len = pos.end - pos.start;
-        let mut buf = vec![0; len];
-        file.read_exact(&mut buf).unwrap();
-        let buf = String::from_utf8(buf).unwrap();
-        eprintln!("\x1b[31m{:IDENT_SIZE$}{buf}\x1b[0m", "");
-        eprintln!("\x1b[31m{:IDENT_SIZE$}{:^<len$}\x1b[0m", "", "");
-      }
-      for extra in diagnostic.extra() {
-        eprintln!("{:IDENT_SIZE$}{}", "", extra);
-      }
+      sink.emit(diagnostic.as_ref(), sources);
+    }
+  }
+}
+
+impl Severity {
+  /// The lowercase name used in machine-readable output.
+  pub fn name(&self) -> &'static str {
+    match self {
+      Severity::Error => "error",
+      Severity::Warning => "warning",
+      Severity::Info => "info",
     }
   }
 }
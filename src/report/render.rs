@@ -0,0 +1,232 @@
+use std::ops::Range;
+
+use unicode_width::UnicodeWidthChar;
+
+use super::{Diagnostic, Severity};
+use crate::{
+  ast::Src,
+  source::{FileId, Source, SourceMap},
+};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const CYAN: &str = "\x1b[1;36m";
+const BLUE: &str = "\x1b[1;34m";
+
+const TAB_SIZE: usize = 2;
+
+/// Spans covering more lines than this have their middle lines elided.
+const MAX_SPAN_LINES: usize = 6;
+
+/// Renders diagnostics as `file:line:col` headers followed by annotated
+/// source snippets.
+pub struct Renderer<'a> {
+  sources: &'a SourceMap,
+  color: bool,
+}
+
+struct Annotation<'a> {
+  file: FileId,
+  range: Range<usize>,
+  marker: char,
+  style: &'a str,
+  label: Option<String>,
+}
+
+impl<'a> Renderer<'a> {
+  pub fn new(sources: &'a SourceMap, color: bool) -> Self {
+    Self { sources, color }
+  }
+
+  fn paint(&self, text: &str, style: &str) -> String {
+    if self.color {
+      format!("{style}{text}{RESET}")
+    } else {
+      text.to_string()
+    }
+  }
+
+  pub fn render(&self, diagnostic: &dyn Diagnostic) -> String {
+    let severity = diagnostic.severity();
+    let style = match severity {
+      Severity::Error => RED,
+      Severity::Warning => YELLOW,
+      Severity::Info => CYAN,
+    };
+
+    let mut annotations = Vec::new();
+    if let Some(Src { file, range }) = diagnostic.src() {
+      annotations.push(Annotation {
+        file,
+        range,
+        marker: '^',
+        style,
+        label: None,
+      });
+    }
+    for (Src { file, range }, label) in diagnostic.labels() {
+      annotations.push(Annotation {
+        file,
+        range,
+        marker: '-',
+        style: BLUE,
+        label: Some(label),
+      });
+    }
+
+    let last_line = annotations
+      .iter()
+      .map(|a| self.sources.get(a.file).line_index(a.range.end) + 2)
+      .max()
+      .unwrap_or(0);
+    let gutter = last_line.to_string().len();
+
+    let mut out = format!(
+      "{}{}\n",
+      self.paint(&format!("[{severity}]"), style),
+      self.paint(&format!(": {}", diagnostic.message()), BOLD),
+    );
+
+    let mut files = Vec::new();
+    for annotation in &annotations {
+      if !files.contains(&annotation.file) {
+        files.push(annotation.file);
+      }
+    }
+
+    for (index, file) in files.into_iter().enumerate() {
+      let source = self.sources.get(file);
+      let annotations = annotations
+        .iter()
+        .filter(|a| a.file == file)
+        .collect::<Vec<_>>();
+      let (line, column) = source.line_col(annotations[0].range.start);
+      let arrow = if index == 0 { "-->" } else { ":::" };
+      out.push_str(&format!(
+        "{:gutter$}{} {}:{line}:{column}\n",
+        "",
+        self.paint(arrow, BLUE),
+        source.name,
+      ));
+      out.push_str(&format!("{:gutter$} {}\n", "", self.paint("|", BLUE)));
+      self.render_snippet(&mut out, source, &annotations, gutter);
+    }
+
+    let extras = diagnostic.extra();
+    if !extras.is_empty() && !annotations.is_empty() {
+      out.push_str(&format!("{:gutter$} {}\n", "", self.paint("|", BLUE)));
+    }
+    for extra in extras {
+      out.push_str(&format!(
+        "{:gutter$} {} {extra}\n",
+        "",
+        self.paint("=", BLUE)
+      ));
+    }
+
+    out
+  }
+
+  fn render_snippet(
+    &self,
+    out: &mut String,
+    source: &Source,
+    annotations: &[&Annotation],
+    gutter: usize,
+  ) {
+    let mut lines = Vec::new();
+    for annotation in annotations {
+      let (first, last) = lines_of(source, &annotation.range);
+      if last - first < MAX_SPAN_LINES {
+        lines.extend(first..=last);
+      } else {
+        lines.extend([first, first + 1, last - 1, last]);
+      }
+    }
+    lines.sort();
+    lines.dedup();
+
+    let first = lines[0];
+    let last = lines[lines.len() - 1];
+    if first > 0 && !source.line(first - 1).trim().is_empty() {
+      lines.insert(0, first - 1);
+    }
+    if last + 1 < source.line_count() && !source.line(last + 1).trim().is_empty() {
+      lines.push(last + 1);
+    }
+
+    let mut previous = None;
+    for line in lines {
+      if previous.is_some_and(|previous| previous + 1 < line) {
+        out.push_str(&format!("{}\n", self.paint("...", BLUE)));
+      }
+      previous = Some(line);
+
+      let text = source.line(line);
+      let number = format!("{:>gutter$}", line + 1);
+      out.push_str(&format!(
+        "{} {} {}\n",
+        self.paint(&number, BLUE),
+        self.paint("|", BLUE),
+        expand_tabs(text),
+      ));
+
+      for &annotation in annotations {
+        let (first, last) = lines_of(source, &annotation.range);
+        if line < first || line > last {
+          continue;
+        }
+
+        let range = &annotation.range;
+        let line_start = source.line_start(line);
+        let start = (range.start.max(line_start) - line_start).min(text.len());
+        let end = range
+          .end
+          .min(line_start + text.len())
+          .saturating_sub(line_start);
+        let padding = visual_width(&text[..start]);
+        let width = visual_width(&text[start..end.max(start)]).max(1);
+
+        let mut underline = std::iter::repeat_n(annotation.marker, width).collect::<String>();
+        if line == last {
+          if let Some(label) = &annotation.label {
+            underline.push(' ');
+            underline.push_str(label);
+          }
+        }
+        out.push_str(&format!(
+          "{:gutter$} {} {:padding$}{}\n",
+          "",
+          self.paint("|", BLUE),
+          "",
+          self.paint(&underline, annotation.style),
+        ));
+      }
+    }
+  }
+}
+
+/// The first and last zero-based lines covered by `range`.
+fn lines_of(source: &Source, range: &Range<usize>) -> (usize, usize) {
+  let first = source.line_index(range.start);
+  let last = source.line_index(range.end.max(range.start + 1) - 1);
+  (first, last.max(first))
+}
+
+fn expand_tabs(text: &str) -> String {
+  text.replace('\t', &" ".repeat(TAB_SIZE))
+}
+
+/// How many columns `text` takes in a terminal, where wide characters such
+/// as CJK ones take two.
+fn visual_width(text: &str) -> usize {
+  text
+    .chars()
+    .map(|c| match c {
+      '\t' => TAB_SIZE,
+      c => c.width().unwrap_or(0),
+    })
+    .sum()
+}
@@ -0,0 +1,143 @@
+use std::io::Write;
+
+use serde_json::{json, Value};
+
+use super::{render::Renderer, Diagnostic, Severity};
+use crate::{ast::Src, source::SourceMap};
+
+/// A consumer of reported diagnostics.
+pub trait Sink {
+  fn emit(&mut self, diagnostic: &dyn Diagnostic, sources: &SourceMap);
+}
+
+/// Prints diagnostics to stderr, either with annotated snippets or as one
+/// `file:line:col: severity: message` line each.
+pub struct TerminalSink {
+  pub color: bool,
+  pub short: bool,
+}
+
+/// Writes each diagnostic as a JSON object on its own line.
+pub struct JsonSink<W: Write> {
+  writer: W,
+}
+
+/// Keeps every diagnostic in memory.
+#[derive(Default)]
+pub struct CollectSink {
+  pub diagnostics: Vec<Collected>,
+}
+
+/// An owned snapshot of a diagnostic.
+#[derive(Clone, Debug)]
+pub struct Collected {
+  pub severity: Severity,
+  pub code: &'static str,
+  pub message: String,
+  pub extra: Vec<String>,
+  pub src: Option<Src>,
+  pub labels: Vec<(Src, String)>,
+}
+
+impl Sink for TerminalSink {
+  fn emit(&mut self, diagnostic: &dyn Diagnostic, sources: &SourceMap) {
+    if !self.short {
+      eprintln!("{}", Renderer::new(sources, self.color).render(diagnostic));
+      return;
+    }
+
+    let location = match diagnostic.src() {
+      Some(Src { file, range }) => {
+        let source = sources.get(file);
+        let (line, column) = source.line_col(range.start);
+        format!("{}:{line}:{column}: ", source.name)
+      }
+      None => String::new(),
+    };
+    eprintln!(
+      "{location}{}: {}",
+      diagnostic.severity().name(),
+      diagnostic.message()
+    );
+  }
+}
+
+impl<W: Write> JsonSink<W> {
+  pub fn new(writer: W) -> Self {
+    Self { writer }
+  }
+}
+
+fn span_to_json(Src { file, range }: &Src, sources: &SourceMap) -> Value {
+  let source = sources.get(*file);
+  let (start_line, start_column) = source.line_col(range.start);
+  let (end_line, end_column) = source.line_col(range.end);
+  json!({
+    "file": source.name,
+    "start": range.start,
+    "end": range.end,
+    "start_line": start_line,
+    "start_column": start_column,
+    "end_line": end_line,
+    "end_column": end_column,
+  })
+}
+
+impl<W: Write> Sink for JsonSink<W> {
+  fn emit(&mut self, diagnostic: &dyn Diagnostic, sources: &SourceMap) {
+    let labels = diagnostic
+      .labels()
+      .iter()
+      .map(|(src, message)| json!({ "message": message, "span": span_to_json(src, sources) }))
+      .collect::<Vec<_>>();
+    let value = json!({
+      "severity": diagnostic.severity().name(),
+      "code": diagnostic.code(),
+      "message": diagnostic.message(),
+      "extra": diagnostic.extra(),
+      "file": diagnostic.src().map(|src| sources.get(src.file).name.clone()),
+      "span": diagnostic.src().map(|src| span_to_json(&src, sources)),
+      "labels": labels,
+    });
+    _ = writeln!(self.writer, "{value}");
+  }
+}
+
+impl Sink for CollectSink {
+  fn emit(&mut self, diagnostic: &dyn Diagnostic, _: &SourceMap) {
+    self.diagnostics.push(Collected {
+      severity: diagnostic.severity(),
+      code: diagnostic.code(),
+      message: diagnostic.message(),
+      extra: diagnostic.extra(),
+      src: diagnostic.src(),
+      labels: diagnostic.labels(),
+    });
+  }
+}
+
+impl Diagnostic for Collected {
+  fn message(&self) -> String {
+    self.message.clone()
+  }
+
+  fn severity(&self) -> Severity {
+    self.severity
+  }
+
+  fn code(&self) -> &'static str {
+    self.code
+  }
+
+  fn extra(&self) -> Vec<String> {
+    self.extra.clone()
+  }
+
+  fn src(&self) -> Option<Src> {
+    self.src.clone()
+  }
+
+  fn labels(&self) -> Vec<(Src, String)> {
+    self.labels.clone()
+  }
+}
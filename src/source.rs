@@ -0,0 +1,88 @@
+/// Identifies a file registered in a [`SourceMap`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FileId(usize);
+
+/// Every source text known to the compiler, whether read from disk or kept
+/// in memory (e.g. an editor buffer).
+#[derive(Default)]
+pub struct SourceMap {
+  files: Vec<Source>,
+}
+
+impl SourceMap {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn add(&mut self, name: String, text: String) -> FileId {
+    self.files.push(Source::new(name, text));
+    FileId(self.files.len() - 1)
+  }
+
+  pub fn get(&self, file: FileId) -> &Source {
+    &self.files[file.0]
+  }
+
+  /// Replaces the text of an already registered file.
+  pub fn update(&mut self, file: FileId, text: String) {
+    let name = std::mem::take(&mut self.files[file.0].name);
+    self.files[file.0] = Source::new(name, text);
+  }
+}
+
+/// A source file's text together with the byte offsets where each line
+/// starts, used to turn byte spans into lines and columns.
+pub struct Source {
+  pub name: String,
+  pub text: String,
+  line_starts: Vec<usize>,
+}
+
+impl Source {
+  pub fn new(name: String, text: String) -> Self {
+    let line_starts = std::iter::once(0)
+      .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+      .collect();
+
+    Self {
+      name,
+      text,
+      line_starts,
+    }
+  }
+
+  pub fn line_count(&self) -> usize {
+    self.line_starts.len()
+  }
+
+  /// The zero-based line containing the byte `offset`.
+  pub fn line_index(&self, offset: usize) -> usize {
+    match self.line_starts.binary_search(&offset) {
+      Ok(line) => line,
+      Err(next) => next - 1,
+    }
+  }
+
+  pub fn line_start(&self, line: usize) -> usize {
+    self.line_starts[line]
+  }
+
+  /// The text of the zero-based `line`, without its line terminator.
+  pub fn line(&self, line: usize) -> &str {
+    let start = self.line_starts[line];
+    let end = self
+      .line_starts
+      .get(line + 1)
+      .copied()
+      .unwrap_or(self.text.len());
+    self.text[start..end].trim_end_matches(['\n', '\r'])
+  }
+
+  /// The one-based line and column (counted in characters) of `offset`.
+  pub fn line_col(&self, offset: usize) -> (usize, usize) {
+    let offset = offset.min(self.text.len());
+    let line = self.line_index(offset);
+    let column = self.text[self.line_starts[line]..offset].chars().count();
+    (line + 1, column + 1)
+  }
+}
@@ -0,0 +1,285 @@
+use std::{
+  path::{Path, PathBuf},
+  process::{Command, Output},
+};
+
+fn check(name: &str, source: &str, args: &[&str]) -> Output {
+  let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("check");
+  std::fs::create_dir_all(&dir).unwrap();
+  let path: PathBuf = dir.join(name);
+  std::fs::write(&path, source).unwrap();
+  Command::new(env!("CARGO_BIN_EXE_oktu"))
+    .args(["check", "--color", "never"])
+    .args(args)
+    .arg(&path)
+    .output()
+    .expect("oktu runs")
+}
+
+#[test]
+fn dumps_typed_program() {
+  let source = "\
+let pair x := (x, \"{x}!\")
+
+let main := let (n, s) = pair \"a\" in n ++ s
+";
+  let output = check("typed.oktu", source, &["--dump-typed"]);
+  assert!(output.status.success(), "{output:?}");
+  assert_eq!(
+    String::from_utf8_lossy(&output.stdout),
+    "\
+let pair : string -> (string, string)
+    1:1  fun : string -> (string, string)
+   1:10    x : string
+   1:15    tuple : (string, string)
+   1:16      x : string
+   1:19      ++ : string
+   1:21        x : string
+   1:23        \"!\" : string
+
+let main : string
+   3:13  let : string
+   3:17    tuple : (string, string)
+   3:18      n : string
+   3:21      s : string
+   3:26    apply : (string, string)
+   3:26      pair : string -> (string, string)
+   3:31      \"a\" : string
+   3:38    ++ : string
+   3:38      n : string
+   3:43      s : string
+
+"
+  );
+}
+
+#[test]
+fn dumps_names_as_written() {
+  let source = "let (<+>) a b := a + b\nlet main := list_length [()] <+> abs 2\n";
+  let output = check("names.oktu", source, &["--dump-typed"]);
+  assert!(output.status.success(), "{output:?}");
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  assert!(
+    stdout.starts_with("let (<+>) : i24 -> i24 -> i24\n"),
+    "{stdout}"
+  );
+  assert!(
+    stdout.contains("      <+> : i24 -> i24 -> i24\n"),
+    "{stdout}"
+  );
+  assert!(
+    stdout.contains("        list_length : list () -> i24\n"),
+    "{stdout}"
+  );
+  assert!(stdout.contains("      abs : i24 -> i24\n"), "{stdout}");
+}
+
+#[test]
+fn names_type_variables_per_declaration() {
+  let output = check(
+    "generic.oktu",
+    "let swap a b := (b, a)\n",
+    &["--dump-typed"],
+  );
+  assert!(output.status.success(), "{output:?}");
+  assert_eq!(
+    String::from_utf8_lossy(&output.stdout),
+    "\
+let swap : 'a -> 'b -> ('b, 'a)
+    1:1  fun : 'a -> 'b -> ('b, 'a)
+   1:10    a : 'a
+    1:1    fun : 'b -> ('b, 'a)
+   1:12      b : 'b
+   1:17      tuple : ('b, 'a)
+   1:18        b : 'b
+   1:21        a : 'a
+
+"
+  );
+}
+
+#[test]
+fn reports_ambiguous_types() {
+  let output = check("ambiguous.oktu", "let main := list_length []\n", &[]);
+  assert!(output.status.success(), "{output:?}");
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  assert!(
+    stderr.contains("The type of this is never fully known."),
+    "{stderr}"
+  );
+  assert!(stderr.contains("It has type list _ -> i24"), "{stderr}");
+}
+
+#[test]
+fn points_past_wide_characters() {
+  let output = check("wide.oktu", "let main := \"日本語\" ++ 1\n", &[]);
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  assert!(
+    stderr.contains("1 | let main := \"日本語\" ++ 1\n  |                         ^\n"),
+    "{stderr}"
+  );
+}
+
+#[test]
+fn keeps_context_of_integer_literals() {
+  let source = "let main := if 1 then 2 else 3\n";
+  let output = check("condition.oktu", source, &["--message-format", "short"]);
+  assert!(!output.status.success(), "{output:?}");
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  assert!(
+    stderr.contains("1:16: error: Condition of `if` must be bool."),
+    "{stderr}"
+  );
+}
+
+#[test]
+fn rejects_negating_unsigned_numbers() {
+  let output = check("unsigned.oktu", "let main := -(i24_to_u24 3)\n", &[]);
+  assert!(!output.status.success(), "{output:?}");
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  assert!(
+    stderr.contains("Only signed numbers can be negated."),
+    "{stderr}"
+  );
+}
+
+#[test]
+fn reports_huge_tuple_indices() {
+  let source = "let main := (1, 2).99999999999999999999999\n";
+  let output = check("index.oktu", source, &["--message-format", "short"]);
+  assert!(!output.status.success(), "{output:?}");
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  assert!(
+    stderr.contains("1:20: error: Tuple index too large."),
+    "{stderr}"
+  );
+}
+
+#[test]
+fn recovers_from_syntax_errors() {
+  let source = "\
+let a := 1 +
+let b := 2
+let c := match b with
+  | 1 => )
+  | _ => 2
+end
+let d := b ++ \"!\"
+";
+  let output = check("recovery.oktu", source, &[]);
+  assert_eq!(output.status.code(), Some(3));
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  // Every syntax error is reported, and what parsed is still checked.
+  for expected in [
+    "Unexpected `let`.",
+    "recovery.oktu:2:1",
+    "Unexpected `)`.",
+    "recovery.oktu:4:10",
+    "Only strings and lists can be concatenated.",
+    "recovery.oktu:7:10",
+  ] {
+    assert!(stderr.contains(expected), "{expected} isn't in {stderr}");
+  }
+}
+
+#[test]
+fn writes_json_diagnostics() {
+  let source = "let x := if true then \"a\" else false\n";
+  let output = check("json.oktu", source, &["--message-format", "json"]);
+  assert_eq!(output.status.code(), Some(4));
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  let lines = stderr.lines().collect::<Vec<_>>();
+  assert_eq!(lines.len(), 1, "{stderr}");
+  let diagnostic: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+  assert_eq!(diagnostic["severity"], "error");
+  assert_eq!(diagnostic["code"], "type-mismatch");
+  assert_eq!(
+    diagnostic["message"],
+    "Both branches of `if` must have the same type."
+  );
+  assert_eq!(diagnostic["extra"][0], "Expected string but got bool.");
+  let span = &diagnostic["span"];
+  assert_eq!(span["start_line"], 1);
+  assert_eq!(span["start_column"], 32);
+  assert_eq!(span["end_line"], 1);
+  assert_eq!(span["end_column"], 37);
+  let label = &diagnostic["labels"][0];
+  assert_eq!(label["message"], "then branch is string here");
+  assert_eq!(label["span"]["start_column"], 23);
+  assert_eq!(label["span"]["end_column"], 26);
+}
+
+#[test]
+fn decides_local_numbers_by_their_uses() {
+  let source = "\
+let f := let y = 5 in u24_to_i24 y
+let g := let add = fun x -> x + 1 in (add 2, add 3.0)
+let h := let n = 1 in n
+";
+  let output = check("local.oktu", source, &["--dump-typed"]);
+  assert!(output.status.success(), "{output:?}");
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  let types = stdout
+    .lines()
+    .filter(|line| line.starts_with("let"))
+    .collect::<Vec<_>>();
+  assert_eq!(types, ["let f : i24", "let g : (f24, f24)", "let h : i24"]);
+}
+
+#[test]
+fn reports_numbers_where_they_go_wrong() {
+  let source = "\
+let a := 1 + \"x\"
+let b := true + 1
+let c := 5 6
+let rec fact n := if n = 0 then 1 else n * fact \"x\"
+";
+  let output = check("operands.oktu", source, &["--message-format", "short"]);
+  assert_eq!(output.status.code(), Some(4));
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  let errors = stderr
+    .lines()
+    .map(|line| line.split_once("operands.oktu:").unwrap().1)
+    .collect::<Vec<_>>();
+  assert_eq!(
+    errors,
+    [
+      "1:14: error: Operands of `+` must be numbers.",
+      "2:10: error: Operands of `+` must be numbers.",
+      "3:10: error: This expression is not a function.",
+      "4:49: error: Argument 1 of `fact` has the wrong type.",
+    ]
+  );
+}
+
+#[test]
+fn checks_the_range_of_integer_literals() {
+  let source = "\
+let a := 99999999999
+let b := 0x1000000
+let c := -8388609
+let d := (-8388608, 8388607, 0x7FFFFF)
+let e := i24_to_u24 0 + 16777215
+let f := i24_to_u24 0 + 16777216
+let g x := match x with | -1 => 0 | 0x10 => 1 | -8388609 => 2 | _ => 3 end
+let h x := match i24_to_u24 x with | 16777215 => 0 | 0xFFFFFF => 1 | 0x1000000 => 2 | _ => 3 end
+";
+  let output = check("ranges.oktu", source, &["--message-format", "short"]);
+  assert_eq!(output.status.code(), Some(4));
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  let errors = stderr
+    .lines()
+    .map(|line| line.split_once("ranges.oktu:").unwrap().1)
+    .collect::<Vec<_>>();
+  assert_eq!(
+    errors,
+    [
+      "1:10: error: Literal out of range for i24.",
+      "2:10: error: Literal out of range for i24.",
+      "3:10: error: Literal out of range for i24.",
+      "6:25: error: Literal out of range for u24.",
+      "7:49: error: Literal out of range for i24.",
+      "8:70: error: Literal out of range for u24.",
+    ]
+  );
+}
@@ -0,0 +1,123 @@
+use std::{
+  path::Path,
+  process::{Command, Output},
+};
+
+fn compile(name: &str, source: &str) -> Output {
+  let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("compile");
+  std::fs::create_dir_all(&dir).unwrap();
+  let path = dir.join(name);
+  std::fs::write(&path, source).unwrap();
+  Command::new(env!("CARGO_BIN_EXE_oktu"))
+    .args(["compile", "--color", "never"])
+    .arg(&path)
+    .output()
+    .expect("oktu runs")
+}
+
+/// Asserts that compiling `source` fails with `code`, printing nothing but
+/// the diagnostic containing `message`.
+fn fails(name: &str, source: &str, code: i32, message: &str) {
+  let output = compile(name, source);
+  assert_eq!(output.status.code(), Some(code), "{output:?}");
+  assert!(output.stdout.is_empty(), "{output:?}");
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  assert!(stderr.contains(message), "{stderr}");
+}
+
+#[test]
+fn prints_the_bend_program() {
+  let output = compile("main.oktu", "let main := 1 + 2\n");
+  assert!(output.status.success(), "{output:?}");
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  assert!(stdout.contains("(main) ="), "{stdout}");
+}
+
+#[test]
+fn fails_to_parse() {
+  fails(
+    "parse.oktu",
+    "let main := (\n",
+    3,
+    "Unexpected end of file.",
+  );
+}
+
+#[test]
+fn fails_to_type() {
+  fails(
+    "type.oktu",
+    "let main := not \"a\"\n",
+    4,
+    "Expected bool but got string.",
+  );
+}
+
+#[test]
+fn fails_to_generate_bend() {
+  let source = "let main := match \"a\" with | \"a\" => 1 | _ => 2 end\n";
+  fails(
+    "backend.oktu",
+    source,
+    5,
+    "Matching on strings is not implemented",
+  );
+}
+
+/// What Bend prints running the compiled `source`, or `None` without
+/// `bend` installed.
+fn run(name: &str, source: &str) -> Option<String> {
+  let output = compile(&format!("{name}.oktu"), source);
+  assert!(output.status.success(), "{output:?}");
+  let path = Path::new(env!("CARGO_TARGET_TMPDIR"))
+    .join("compile")
+    .join(format!("{name}.bend"));
+  std::fs::write(&path, &output.stdout).unwrap();
+  let Ok(output) = Command::new("bend").arg("run").arg(&path).output() else {
+    eprintln!("skipped: `bend` is not installed");
+    return None;
+  };
+  assert!(output.status.success(), "{output:?}");
+  Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[test]
+fn keeps_generated_names_apart_from_variables() {
+  let source = "\
+let f _oktu_scrutinee := match 1 with | 1 => _oktu_scrutinee | _ => 0 end
+let g _oktu_element := (10, 20).1 + _oktu_element
+let h _oktu_first := (-1, _oktu_first).1
+let main := (f 42, g 3, h 4)
+";
+  if let Some(stdout) = run("names", source) {
+    assert!(stdout.contains("Result: (+42, (+23, +4))"), "{stdout}");
+  }
+}
+
+#[test]
+fn converts_between_numbers_and_strings() {
+  let source = "\
+let main := (
+  number_to_string (-8388608),
+  [string_to_number \"-8388608\", string_to_number \"12a\", string_to_number \"8388608\"]
+)
+";
+  if let Some(stdout) = run("numbers", source) {
+    assert!(
+      stdout.contains("Result: (\"-8388608\", [[-8388608], [], []])"),
+      "{stdout}"
+    );
+  }
+}
+
+#[test]
+fn keeps_operators_apart_from_names() {
+  let source = "\
+let (<+>) a b := a + b
+let op_less_plus_greater := 99
+let main := (1 <+> 2, op_less_plus_greater)
+";
+  if let Some(stdout) = run("operators", source) {
+    assert!(stdout.contains("Result: (+3, +99)"), "{stdout}");
+  }
+}
@@ -0,0 +1,95 @@
+use std::{
+  path::{Path, PathBuf},
+  process::{Command, Output},
+};
+
+fn eval(path: &Path) -> Output {
+  Command::new(env!("CARGO_BIN_EXE_oktu"))
+    .arg("eval")
+    .arg(path)
+    .output()
+    .expect("oktu runs")
+}
+
+fn write(name: &str, source: &str) -> PathBuf {
+  let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("eval");
+  std::fs::create_dir_all(&dir).unwrap();
+  let path = dir.join(name);
+  std::fs::write(&path, source).unwrap();
+  path
+}
+
+#[test]
+fn evaluates_main() {
+  let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/eval/sample.oktu");
+  let output = eval(&path);
+  assert!(output.status.success(), "{output:?}");
+  assert_eq!(
+    String::from_utf8_lossy(&output.stdout),
+    "Hello, Oktu!\n\
+     (4, 3, 7, 103, 500500, -8388608, -4, [('o', \"k\")], true, 1.5, <fun>)\n"
+  );
+}
+
+#[test]
+fn reports_runtime_errors() {
+  let path = write("division.oktu", "let half x := x / 0\nlet main := half 1\n");
+  let output = eval(&path);
+  assert_eq!(output.status.code(), Some(7));
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  assert!(stderr.contains("Division by zero."), "{stderr}");
+  assert!(stderr.contains("division.oktu:1:15"), "{stderr}");
+}
+
+#[test]
+fn computes_like_hvm() {
+  let source = "let main := (1 << 33, -8 >> 33, 7.0 / 0.0, 7.0 % 0.0)\n";
+  let output = eval(&write("hvm.oktu", source));
+  assert!(output.status.success(), "{output:?}");
+  assert_eq!(
+    String::from_utf8_lossy(&output.stdout),
+    "(2, -4, inf, NaN)\n"
+  );
+}
+
+#[test]
+fn binds_underscored_names_and_nested_tuples() {
+  let source = "\
+let first := fun ((a, _), _c) -> a + _c
+let main := let _z = 1 in (first ((2, 3), 4), (fun _x -> _x) _z)
+";
+  let output = eval(&write("binders.oktu", source));
+  assert!(output.status.success(), "{output:?}");
+  assert_eq!(String::from_utf8_lossy(&output.stdout), "(6, 1)\n");
+}
+
+#[test]
+fn converts_between_numbers_and_strings() {
+  let source = "\
+let main := (
+  [number_to_string (-8388608), number_to_string 8388607, number_to_string 0],
+  [string_to_number \"-8388608\", string_to_number \"8388607\", string_to_number \"0\"],
+  [string_to_number \"x\", string_to_number \"12a\", string_to_number \"99999999\"],
+  [string_to_number \"8388608\", string_to_number \"-\", string_to_number \"\"]
+)
+";
+  let output = eval(&write("numbers.oktu", source));
+  assert!(output.status.success(), "{output:?}");
+  assert_eq!(
+    String::from_utf8_lossy(&output.stdout),
+    "([\"-8388608\", \"8388607\", \"0\"], [[-8388608], [8388607], [0]], \
+     [[], [], []], [[], [], []])\n"
+  );
+}
+
+#[test]
+fn keeps_operators_apart_from_names() {
+  let source = "\
+let (<+>) a b := a + b
+let op_less_plus_greater := 99
+let main := (1 <+> 2, op_less_plus_greater)
+";
+  let output = eval(&write("operators.oktu", source));
+  assert!(output.status.success(), "{output:?}");
+  assert_eq!(String::from_utf8_lossy(&output.stdout), "(3, 99)\n");
+}
@@ -0,0 +1,75 @@
+use std::{
+  fs,
+  path::{Path, PathBuf},
+  process::{Command, Output},
+};
+
+fn oktu(args: &[&str], path: &Path) -> Output {
+  Command::new(env!("CARGO_BIN_EXE_oktu"))
+    .args(args)
+    .arg(path)
+    .output()
+    .expect("oktu runs")
+}
+
+/// A copy of `source` in a scratch directory, so it can be formatted in place.
+fn scratch(name: &str, source: &str) -> PathBuf {
+  let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("fmt");
+  fs::create_dir_all(&dir).unwrap();
+  let path = dir.join(name);
+  fs::write(&path, source).unwrap();
+  path
+}
+
+/// Formatting checks that the output parses back to the same program, so
+/// this covers the round trip. It also has to be idempotent.
+#[test]
+fn formats_round_trip() {
+  let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+  for file in ["tests/fmt/sample.oktu", "src/prelude.oktu", "example.oktu"] {
+    let source = fs::read_to_string(manifest.join(file)).unwrap();
+    let path = scratch(&file.replace('/', "_"), &source);
+
+    let output = oktu(&["fmt"], &path);
+    assert!(output.status.success(), "{file}: {output:?}");
+    let formatted = fs::read_to_string(&path).unwrap();
+
+    let output = oktu(&["fmt", "--check"], &path);
+    assert!(
+      output.status.success(),
+      "{file} isn't formatted after fmt: {output:?}"
+    );
+    oktu(&["fmt"], &path);
+    assert_eq!(formatted, fs::read_to_string(&path).unwrap(), "{file}");
+  }
+}
+
+#[test]
+fn check_lists_unformatted_files() {
+  let source = "let   f x :=x+1\n";
+  let path = scratch("unformatted.oktu", source);
+
+  let output = oktu(&["fmt", "--check"], &path);
+  assert_eq!(output.status.code(), Some(6));
+  assert!(String::from_utf8_lossy(&output.stdout).contains("unformatted.oktu"));
+  assert_eq!(source, fs::read_to_string(&path).unwrap());
+
+  oktu(&["fmt"], &path);
+  assert_eq!("let f x := x + 1\n", fs::read_to_string(&path).unwrap());
+}
+
+#[test]
+fn keeps_numbers_as_written() {
+  let source = "\
+let n := (0xFF, 0b11, 1_000, - 0x10, 2.5_0)
+let m x := match x with | 0xFF => 1 | -0b1 => 2 | _ => 1_0 end
+";
+  let path = scratch("numbers.oktu", source);
+  let output = oktu(&["fmt"], &path);
+  assert!(output.status.success(), "{output:?}");
+  assert_eq!(
+    fs::read_to_string(&path).unwrap(),
+    "let n := (0xFF, 0b11, 1_000, -0x10, 2.5_0)\n\
+     let m x := match x with | 0xFF => 1 | -0b1 => 2 | _ => 1_0 end\n"
+  );
+}
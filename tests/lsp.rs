@@ -0,0 +1,185 @@
+use std::{
+  io::Write,
+  path::Path,
+  process::{Command, Stdio},
+};
+
+use serde_json::{json, Value};
+
+const DOCUMENT: &str = "\
+let rec len xs :=
+  match xs with
+  | [] => 0
+  | _ :: rest => 1 + len rest
+  end
+
+let main := len [true] + nope
+";
+
+fn frame(message: Value) -> Vec<u8> {
+  let body = message.to_string();
+  format!("Content-Length: {}\r\n\r\n{body}", body.len()).into_bytes()
+}
+
+/// The messages the server answers a whole session with.
+fn session(messages: Vec<Value>) -> Vec<Value> {
+  let mut server = Command::new(env!("CARGO_BIN_EXE_oktu"))
+    .arg("lsp")
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .expect("oktu runs");
+  let mut stdin = server.stdin.take().unwrap();
+  for message in messages {
+    stdin.write_all(&frame(message)).unwrap();
+  }
+  drop(stdin);
+  let output = server.wait_with_output().unwrap();
+  assert!(output.status.success(), "{output:?}");
+
+  let mut replies = Vec::new();
+  let mut rest = String::from_utf8(output.stdout).unwrap();
+  while let Some((header, body)) = rest.split_once("\r\n\r\n") {
+    let length = header["Content-Length: ".len()..].parse::<usize>().unwrap();
+    replies.push(serde_json::from_str(&body[..length]).unwrap());
+    rest = body[length..].to_string();
+  }
+  replies
+}
+
+const URI: &str = "file:///tmp/doc.oktu";
+
+fn at(uri: &str, id: u32, method: &str, line: u32, character: u32) -> Value {
+  json!({
+    "jsonrpc": "2.0",
+    "id": id,
+    "method": method,
+    "params": {
+      "textDocument": { "uri": uri },
+      "position": { "line": line, "character": character },
+    },
+  })
+}
+
+fn open(uri: &str, text: &str) -> Value {
+  json!({
+    "jsonrpc": "2.0",
+    "method": "textDocument/didOpen",
+    "params": {
+      "textDocument": { "uri": uri, "languageId": "oktu", "version": 1, "text": text },
+    },
+  })
+}
+
+fn initialize() -> [Value; 2] {
+  [
+    json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": { "capabilities": {} } }),
+    json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} }),
+  ]
+}
+
+fn shutdown(id: u32) -> [Value; 2] {
+  [
+    json!({ "jsonrpc": "2.0", "id": id, "method": "shutdown" }),
+    json!({ "jsonrpc": "2.0", "method": "exit" }),
+  ]
+}
+
+fn reply(replies: &[Value], id: u32) -> &Value {
+  replies
+    .iter()
+    .find(|reply| reply["id"] == id)
+    .unwrap_or_else(|| panic!("no reply {id} in {replies:?}"))
+}
+
+#[test]
+fn answers_a_session() {
+  let mut messages = initialize().to_vec();
+  messages.push(open(URI, DOCUMENT));
+  messages.push(at(URI, 2, "textDocument/hover", 3, 26));
+  messages.push(at(URI, 3, "textDocument/definition", 3, 25));
+  messages.push(at(URI, 4, "textDocument/completion", 3, 19));
+  messages.extend(shutdown(5));
+  let replies = session(messages);
+  let reply = |id: u32| reply(&replies, id);
+
+  let diagnostics = replies
+    .iter()
+    .find(|reply| reply["method"] == "textDocument/publishDiagnostics")
+    .expect("diagnostics are published");
+  let diagnostics = &diagnostics["params"]["diagnostics"];
+  assert_eq!(diagnostics[0]["code"], "unbound-variable");
+  assert_eq!(
+    diagnostics[0]["range"]["start"],
+    json!({ "line": 6, "character": 25 })
+  );
+
+  assert_eq!(
+    reply(2)["result"]["contents"]["value"],
+    "```oktu\nlist 'a\n```"
+  );
+  assert_eq!(
+    reply(3)["result"]["range"],
+    json!({ "start": { "line": 3, "character": 9 }, "end": { "line": 3, "character": 13 } })
+  );
+  let labels = reply(4)["result"]
+    .as_array()
+    .unwrap()
+    .iter()
+    .map(|item| item["label"].as_str().unwrap())
+    .collect::<Vec<_>>();
+  for name in ["rest", "xs", "len", "main", "print"] {
+    assert!(labels.contains(&name), "{name} isn't in {labels:?}");
+  }
+}
+
+#[test]
+fn goes_to_definitions_in_other_files() {
+  let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("lsp");
+  std::fs::create_dir_all(&dir).unwrap();
+  let module = dir.join("geometry.oktu");
+  std::fs::write(&module, "pub let area w h := w * h\n").unwrap();
+  let document = dir.join("main.oktu");
+  let uri = format!("file://{}", document.display());
+  let text = "import geometry\nlet main := geometry.area 2 3 + abs 1\n";
+
+  let mut messages = initialize().to_vec();
+  messages.push(open(&uri, text));
+  messages.push(at(&uri, 2, "textDocument/definition", 1, 22));
+  messages.push(at(&uri, 3, "textDocument/definition", 1, 33));
+  messages.extend(shutdown(4));
+  let replies = session(messages);
+
+  let area = &reply(&replies, 2)["result"];
+  assert_eq!(area["uri"], format!("file://{}", module.display()));
+  assert_eq!(area["range"]["start"], json!({ "line": 0, "character": 0 }));
+  let abs = &reply(&replies, 3)["result"];
+  let path = abs["uri"]
+    .as_str()
+    .unwrap()
+    .strip_prefix("file://")
+    .unwrap();
+  let prelude = std::fs::read_to_string(path).unwrap();
+  let line = abs["range"]["start"]["line"].as_u64().unwrap() as usize;
+  let definition = prelude.lines().nth(line).unwrap();
+  assert!(definition.starts_with("pub let abs "), "{definition}");
+}
+
+#[test]
+fn ignores_malformed_notifications() {
+  let mut messages = initialize().to_vec();
+  messages.push(json!({
+    "jsonrpc": "2.0",
+    "method": "textDocument/didOpen",
+    "params": { "textDocument": 42 },
+  }));
+  messages.push(open(URI, DOCUMENT));
+  messages.push(at(URI, 2, "textDocument/hover", 3, 26));
+  messages.extend(shutdown(3));
+  let replies = session(messages);
+  assert_eq!(
+    reply(&replies, 2)["result"]["contents"]["value"],
+    "```oktu\nlist 'a\n```"
+  );
+}
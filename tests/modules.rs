@@ -0,0 +1,112 @@
+use std::{
+  path::{Path, PathBuf},
+  process::{Command, Output},
+};
+
+/// Writes the `files` of a program into a directory of their own, returning
+/// the path of the first one.
+fn program(dir: &str, files: &[(&str, &str)]) -> PathBuf {
+  let dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+    .join("modules")
+    .join(dir);
+  std::fs::create_dir_all(&dir).unwrap();
+  for (name, source) in files {
+    std::fs::write(dir.join(name), source).unwrap();
+  }
+  dir.join(files[0].0)
+}
+
+fn oktu(command: &str, path: &Path) -> Output {
+  Command::new(env!("CARGO_BIN_EXE_oktu"))
+    .args([command, "--color", "never"])
+    .arg(path)
+    .output()
+    .expect("oktu runs")
+}
+
+const GEOMETRY: &str = "\
+pub let area w h := w * h
+let secret := 42
+";
+
+#[test]
+fn imports_qualified_names_and_items() {
+  let main = "\
+import geometry
+import geometry.{area}
+
+let main := area 2 3 + geometry.area 4 5
+";
+  let path = program(
+    "imports",
+    &[("main.oktu", main), ("geometry.oktu", GEOMETRY)],
+  );
+  let output = oktu("eval", &path);
+  assert!(output.status.success(), "{output:?}");
+  assert_eq!(String::from_utf8_lossy(&output.stdout), "26\n");
+}
+
+#[test]
+fn keeps_private_definitions_private() {
+  let main = "\
+import geometry
+import geometry.{secret}
+
+let main := geometry.secret
+";
+  let path = program(
+    "private",
+    &[("main.oktu", main), ("geometry.oktu", GEOMETRY)],
+  );
+  let output = oktu("check", &path);
+  assert_eq!(output.status.code(), Some(4));
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  let private = "'secret' is private to module 'geometry'.";
+  assert_eq!(stderr.matches(private).count(), 2, "{stderr}");
+  assert!(stderr.contains("main.oktu:2:1"), "{stderr}");
+  assert!(stderr.contains("main.oktu:4:13"), "{stderr}");
+  assert!(
+    stderr.contains("Mark it with `pub` to export it."),
+    "{stderr}"
+  );
+  assert!(!stderr.contains("Unbound variable"), "{stderr}");
+}
+
+#[test]
+fn reports_import_cycles() {
+  let path = program(
+    "cycle",
+    &[
+      ("main.oktu", "import list\nlet main := list.x\n"),
+      ("list.oktu", "import cyc\npub let x := 1\n"),
+      ("cyc.oktu", "import list\npub let y := 2\n"),
+    ],
+  );
+  let output = oktu("check", &path);
+  assert_eq!(output.status.code(), Some(4));
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  assert!(stderr.contains("Import cycle detected."), "{stderr}");
+  assert!(stderr.contains("cyc.oktu:1:1"), "{stderr}");
+  assert!(stderr.contains("list -> cyc -> list."), "{stderr}");
+}
+
+#[test]
+fn links_shared_imports_once() {
+  let path = program(
+    "diamond",
+    &[
+      ("main.oktu", "import a\nimport b\nlet main := (a.a, b.b)\n"),
+      ("a.oktu", "import c\npub let a := c.v + 1\n"),
+      ("b.oktu", "import c\npub let b := c.v + 2\n"),
+      ("c.oktu", "pub let v := 1\n"),
+    ],
+  );
+  let output = oktu("eval", &path);
+  assert!(output.status.success(), "{output:?}");
+  assert_eq!(String::from_utf8_lossy(&output.stdout), "(2, 3)\n");
+
+  let output = oktu("compile", &path);
+  assert!(output.status.success(), "{output:?}");
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  assert_eq!(stdout.matches("(c.v) =").count(), 1, "{stdout}");
+}
@@ -0,0 +1,162 @@
+use std::{
+  io::{BufRead, BufReader, Read, Write},
+  path::{Path, PathBuf},
+  process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+/// A running `oktu repl`, fed one line at a time.
+struct Session {
+  child: Child,
+  stdin: Option<ChildStdin>,
+  stdout: BufReader<ChildStdout>,
+}
+
+impl Session {
+  fn start() -> Self {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_oktu"))
+      .args(["--color", "never", "repl"])
+      .stdin(Stdio::piped())
+      .stdout(Stdio::piped())
+      .stderr(Stdio::piped())
+      .spawn()
+      .expect("oktu runs");
+    let stdin = child.stdin.take();
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+    Self {
+      child,
+      stdin,
+      stdout,
+    }
+  }
+
+  fn send(&mut self, lines: &str) {
+    let stdin = self.stdin.as_mut().unwrap();
+    stdin.write_all(lines.as_bytes()).unwrap();
+    stdin.flush().unwrap();
+  }
+
+  /// Reads the output up to the end of the first line containing `text`.
+  fn wait_for(&mut self, text: &str) -> String {
+    let mut output = String::new();
+    while !output.lines().any(|line| line.contains(text)) {
+      let read = self.stdout.read_line(&mut output).unwrap();
+      assert!(read > 0, "the session ended before {text:?}: {output}");
+    }
+    output
+  }
+
+  /// Ends the input and returns the rest of the output and the diagnostics.
+  fn finish(mut self) -> (String, String) {
+    drop(self.stdin.take());
+    let mut stdout = String::new();
+    self.stdout.read_to_string(&mut stdout).unwrap();
+    let mut stderr = String::new();
+    self
+      .child
+      .stderr
+      .take()
+      .unwrap()
+      .read_to_string(&mut stderr)
+      .unwrap();
+    assert!(self.child.wait().unwrap().success());
+    (stdout, stderr)
+  }
+}
+
+fn write(name: &str, source: &str) -> PathBuf {
+  let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("repl");
+  std::fs::create_dir_all(&dir).unwrap();
+  let path = dir.join(name);
+  std::fs::write(&path, source).unwrap();
+  path
+}
+
+/// Each output line, without the prompts printed before it.
+fn lines(output: &str) -> Vec<&str> {
+  output
+    .lines()
+    .map(|line| line.trim_start_matches("oktu> "))
+    .filter(|line| !line.is_empty())
+    .collect()
+}
+
+#[test]
+fn evaluates_and_types_inputs() {
+  let path = write("session.oktu", "let double x := x * 2\n");
+  let mut session = Session::start();
+  session.send(&format!(":load {}\n", path.display()));
+  session.send("double 21\n:type double\nlet triple x := x * 3\n:browse\n");
+  session.send(":type (it, triple)\n");
+  let (stdout, stderr) = session.finish();
+  assert_eq!(
+    lines(&stdout),
+    [
+      format!("Loaded {}.", path.display()).as_str(),
+      "42 : i24",
+      "i24 -> i24",
+      "double : i24 -> i24",
+      "triple : i24 -> i24",
+      "(i24, i24 -> i24)",
+    ]
+  );
+  assert!(stderr.is_empty(), "{stderr}");
+}
+
+#[test]
+fn reloads_the_loaded_file() {
+  let path = write("reloaded.oktu", "let answer := 41\n");
+  let mut session = Session::start();
+  session.send(&format!(":load {}\n", path.display()));
+  session.wait_for("Loaded");
+  std::fs::write(&path, "let answer := 42\nlet greeting := \"hi\"\n").unwrap();
+  session.send(":reload\nanswer\n:browse\n");
+  let (stdout, stderr) = session.finish();
+  assert_eq!(
+    lines(&stdout),
+    [
+      format!("Loaded {}.", path.display()).as_str(),
+      "42 : i24",
+      "answer : i24",
+      "greeting : string",
+    ]
+  );
+  assert!(stderr.is_empty(), "{stderr}");
+}
+
+#[test]
+fn reports_errors_and_goes_on() {
+  let mut session = Session::start();
+  session.send(":reload\n1 + true\n:frobnicate\n1 + 1\n");
+  let (stdout, stderr) = session.finish();
+  assert_eq!(lines(&stdout), ["2 : i24"]);
+  assert!(stderr.contains("No file was loaded yet."), "{stderr}");
+  assert!(
+    stderr.contains("Operands of `+` must be numbers."),
+    "{stderr}"
+  );
+  assert!(
+    stderr.contains("Unknown command `:frobnicate`."),
+    "{stderr}"
+  );
+}
+
+#[test]
+fn goes_on_after_inputs_bend_cannot_compile() {
+  let mut session = Session::start();
+  session.send("enum color := .red, .green\n.red\n:type .green\n");
+  session.send("match \"a\" with | \"a\" => 1 | _ => 2 end\nlet z = 3 in z\n");
+  let (stdout, stderr) = session.finish();
+  assert_eq!(
+    lines(&stdout),
+    ["it : color", "color", "it : i24", "3 : i24"]
+  );
+  assert!(
+    stderr.contains("Could not compile the expression to Bend: Variants are not implemented"),
+    "{stderr}"
+  );
+  assert!(
+    stderr.contains("Matching on strings is not implemented"),
+    "{stderr}"
+  );
+  assert!(!stderr.contains("hvm"), "{stderr}");
+}